@@ -0,0 +1,192 @@
+#[cfg(all(target_arch = "x86_64", target_feature = "bmi2"))]
+use bitintr::*;
+
+use crate::constants::*;
+
+lazy_static! {
+    pub static ref SLIDER_ATTACKS: SliderAttacks = SliderAttacks::new();
+}
+
+/// Squares attacked by a rooklike piece at pos_tzcnt, including the first blocker in each direction
+#[inline]
+pub fn rook_attacks(pos_tzcnt: u8, occupancy: u64) -> u64 {
+    SLIDER_ATTACKS.rook.attacks(pos_tzcnt, occupancy)
+}
+
+/// Squares attacked by a bishoplike piece at pos_tzcnt, including the first blocker in each direction
+#[inline]
+pub fn bishop_attacks(pos_tzcnt: u8, occupancy: u64) -> u64 {
+    SLIDER_ATTACKS.bishop.attacks(pos_tzcnt, occupancy)
+}
+
+pub struct SliderAttacks {
+    rook: SliderTable,
+    bishop: SliderTable,
+}
+
+impl SliderAttacks {
+    fn new() -> Self {
+        SliderAttacks {
+            rook: SliderTable::new(&ROOK_MAGICS, slow_rook_attacks),
+            bishop: SliderTable::new(&BISHOP_MAGICS, slow_bishop_attacks),
+        }
+    }
+}
+
+/// Attack sets for every square and every occupancy of the squares that can block the slider,
+/// indexed by magic multiplication https://www.chessprogramming.org/Magic_Bitboards or by PEXT
+/// when compiled with BMI2 support
+#[cfg_attr(
+    all(target_arch = "x86_64", target_feature = "bmi2"),
+    allow(dead_code) // The magics are only used without PEXT
+)]
+struct SliderTable {
+    /// Squares that can block the slider, the board edge never blocks anything behind it
+    masks: [u64; 64],
+    magics: [u64; 64],
+    shifts: [u8; 64],
+    /// Start of each square's attack sets in attacks
+    offsets: [usize; 64],
+    attacks: Vec<u64>,
+}
+
+impl SliderTable {
+    fn new(magics: &[u64; 64], slow_attacks: fn(u8, u64) -> u64) -> Self {
+        let mut table = SliderTable {
+            masks: [0; 64],
+            magics: *magics,
+            shifts: [0; 64],
+            offsets: [0; 64],
+            attacks: Vec::new(),
+        };
+
+        for square in 0..64u8 {
+            let s = square as usize;
+            let row = ROWS[s / 8];
+            let file = FILES[s % 8];
+            let edges = ((ROW_1 | ROW_8) & !row) | ((FILE_A | FILE_H) & !file);
+            let mask = slow_attacks(square, 0) & !edges;
+
+            table.masks[s] = mask;
+            table.shifts[s] = (64 - mask.count_ones()) as u8;
+            table.offsets[s] = table.attacks.len();
+            table
+                .attacks
+                .resize(table.attacks.len() + (1 << mask.count_ones()), 0);
+
+            // Every subset of the mask https://www.chessprogramming.org/Traversing_Subsets_of_a_Set
+            let mut occupancy = 0u64;
+            loop {
+                let index = table.index(s, occupancy);
+                let attacks = slow_attacks(square, occupancy);
+                // A bad magic maps occupancies with different attacks to the same slot
+                debug_assert!(
+                    table.attacks[index] == 0 || table.attacks[index] == attacks,
+                    "Magic collision on square {}",
+                    square
+                );
+                table.attacks[index] = attacks;
+                occupancy = occupancy.wrapping_sub(mask) & mask;
+                if occupancy == 0 {
+                    break;
+                }
+            }
+        }
+
+        table
+    }
+
+    #[inline]
+    fn attacks(&self, pos_tzcnt: u8, occupancy: u64) -> u64 {
+        self.attacks[self.index(pos_tzcnt as usize, occupancy)]
+    }
+
+    #[cfg(all(target_arch = "x86_64", target_feature = "bmi2"))]
+    #[inline]
+    fn index(&self, square: usize, occupancy: u64) -> usize {
+        self.offsets[square] + occupancy.pext(self.masks[square]) as usize
+    }
+
+    #[cfg(not(all(target_arch = "x86_64", target_feature = "bmi2")))]
+    #[inline]
+    fn index(&self, square: usize, occupancy: u64) -> usize {
+        let relevant = occupancy & self.masks[square];
+        self.offsets[square]
+            + (relevant.wrapping_mul(self.magics[square]) >> self.shifts[square]) as usize
+    }
+}
+
+fn slow_rook_attacks(pos_tzcnt: u8, occupancy: u64) -> u64 {
+    let position = 1u64 << pos_tzcnt;
+    slide(position, occupancy, 8, ROW_8)
+        | slide(position, occupancy, -8, ROW_1)
+        | slide(position, occupancy, 1, FILE_H)
+        | slide(position, occupancy, -1, FILE_A)
+}
+
+fn slow_bishop_attacks(pos_tzcnt: u8, occupancy: u64) -> u64 {
+    let position = 1u64 << pos_tzcnt;
+    slide(position, occupancy, 9, ROW_8 | FILE_H)
+        | slide(position, occupancy, 7, ROW_8 | FILE_A)
+        | slide(position, occupancy, -7, ROW_1 | FILE_H)
+        | slide(position, occupancy, -9, ROW_1 | FILE_A)
+}
+
+fn slide(position: u64, occupancy: u64, shift: i8, edge: u64) -> u64 {
+    let mut attacks = 0;
+    let mut target_pos = position;
+    while !intersects(target_pos, edge) {
+        target_pos = if shift > 0 {
+            target_pos << shift
+        } else {
+            target_pos >> -shift
+        };
+        attacks |= target_pos;
+        if intersects(target_pos, occupancy) {
+            break;
+        }
+    }
+    attacks
+}
+
+/// Found by trial with a fixed seed, each maps every relevant occupancy of its square to an index
+/// without destructive collisions
+#[rustfmt::skip]
+const ROOK_MAGICS: [u64; 64] = [
+    0x1080_0040_0880_1020, 0x0840_0920_02C0_3000, 0x1900_2000_1040_0900, 0x0880_1000_0800_0480,
+    0x4200_1004_2008_0200, 0x8100_0201_0008_0400, 0x0200_0401_1088_6200, 0x0200_0080_4022_0411,
+    0x0404_8000_8440_0220, 0x0000_4010_0040_2000, 0x0086_0010_8122_0440, 0x0408_8008_0010_0280,
+    0x000A_0012_0104_0820, 0x8848_8002_0084_0080, 0x4001_0001_0004_0200, 0x0442_0001_0210_5084,
+    0x9080_0100_2080_4100, 0x0040_4040_0020_1009, 0x0000_8080_1000_2009, 0x2200_0900_21D0_0100,
+    0x0008_0080_0804_0080, 0x0004_0040_0201_0040, 0x0011_0400_0801_5042, 0x0000_0A00_0176_8104,
+    0x0000_8000_8020_4009, 0x2010_0041_4000_2001, 0x9800_2002_8010_0080, 0x1000_1000_8008_0080,
+    0x0442_000A_0004_9020, 0x2100_0400_8002_0080, 0x0800_1204_0090_0148, 0x0010_040A_0012_8541,
+    0x2800_8040_0080_0030, 0x1010_0020_0040_0041, 0x4000_2000_1100_4100, 0x0610_0084_1080_0800,
+    0x0400_8024_0280_0800, 0xC100_0200_8080_0400, 0x0002_0008_0200_0401, 0x0182_0858_8200_0401,
+    0x0220_2040_0080_8000, 0x2860_1000_4002_4022, 0x0001_0020_0411_0040, 0x9910_1042_000A_0020,
+    0x0004_0800_0400_8080, 0x0010_0400_0200_8080, 0x2012_0048_8102_0004, 0x8300_8424_4482_0011,
+    0x0088_4038_8201_0200, 0x0820_4000_8021_0100, 0x0110_9100_40A0_0300, 0x0801_1002_8008_0480,
+    0x0242_0090_0820_0600, 0x1002_0004_8950_0200, 0x0040_8002_0001_0080, 0x0091_8000_4100_0080,
+    0x0000_2093_0048_8001, 0x04C1_0024_1482_4001, 0x0200_2000_0B00_1041, 0x7000_1000_0420_0901,
+    0x8002_0020_0410_0802, 0x3001_0002_084C_0007, 0x0888_2218_0081_3004, 0x4000_0028_4084_0112,
+];
+
+#[rustfmt::skip]
+const BISHOP_MAGICS: [u64; 64] = [
+    0xA010_0411_0800_3100, 0x0060_8202_0A00_2900, 0x6810_0106_1920_0000, 0x0828_1A05_2000_0408,
+    0x0001_1040_0100_0400, 0x0018_9010_0804_8400, 0x0004_0A02_1024_5280, 0x0002_0021_0808_A402,
+    0x9140_0484_1082_1200, 0x0800_0910_1082_0041, 0x2050_4804_8322_02C0, 0x0100_0914_0108_1000,
+    0x8021_0111_4000_0012, 0x0810_0208_0445_0400, 0x208B_0542_1090_08A2, 0x0080_084A_0804_0204,
+    0x0040_E2A8_0811_244C, 0x2505_0220_0800_8108, 0x0430_2201_0042_0040, 0x010A_0404_2022_0040,
+    0x1105_0002_9040_0000, 0x0093_0012_0082_2120, 0x4000_A620_4804_3004, 0x2801_2004_8A01_5004,
+    0x0060_9000_2A02_0814, 0x4404_2000_2408_00D0, 0x0110_2800_040A_4400, 0x1004_0800_8022_0040,
+    0x0001_0010_1100_4024, 0x0010_0440_0080_5040, 0x0914_0412_0082_0100, 0x0004_8210_1282_1480,
+    0x0024_0405_00C0_5021, 0x0088_6110_0208_0200, 0x0116_080A_0004_0020, 0x4000_0200_8008_0080,
+    0x2450_4501_4084_0040, 0x0000_8802_0148_4100, 0x0222_0204_0402_0092, 0x8081_1106_0000_2E00,
+    0x2842_1011_0500_0801, 0x1100_8090_0800_1025, 0x0002_0202_221C_0400, 0x0422_0140_2200_9020,
+    0x0210_0461_0210_0C00, 0xC004_0080_8202_9102, 0x00AA_4618_0110_1200, 0x0404_0800_8020_1108,
+    0x0205_4210_8C20_5002, 0x0410_5448_0410_0100, 0x0040_9108_4110_0000, 0x0400_2000_4202_1100,
+    0x0000_4204_8504_00C0, 0x0200_1004_10A4_2102, 0x1040_0208_0121_0102, 0x0805_0404_1042_0000,
+    0x2884_8041_3010_0200, 0x800C_2622_0124_2000, 0x1058_0001_9410_8800, 0x0014_2210_5442_0204,
+    0x0104_0000_12A0_2200, 0x0200_8810_0330_0100, 0x0140_4002_0284_0100, 0x0402_0208_0101_0201,
+];
@@ -1,4 +1,6 @@
 use fisk::board::Board;
+use fisk::search::{Search, SearchFeatures};
+use fisk::transposition::{TranspositionTable, DEFAULT_TT_SIZE_MB};
 
 fn count_nodes(board: &Board, depth: i32) -> u32 {
     if depth == 0 {
@@ -54,21 +56,83 @@ pub fn bench_movegen(depth: i32, use_iterator: bool, starting_board: Option<&str
     );
 }
 
+/// Search with all features, then with each heuristic turned off, to compare node counts
 pub fn bench_search(depth: i32, starting_board: Option<&str>) {
     println!("Benchmarking search with depth={}", depth);
     let board = starting_board
         .map(|fen| Board::from_fen(fen).expect("Invalid starting board"))
         .unwrap_or_else(Board::default);
 
-    let t1 = time::get_time();
-    board.best_move(depth as usize);
-    let t2 = time::get_time();
+    let all = SearchFeatures::default();
+    let configurations = [
+        ("All features", all),
+        (
+            "Without killers and history",
+            SearchFeatures {
+                quiet_move_ordering: false,
+                ..all
+            },
+        ),
+        (
+            "Without null move pruning",
+            SearchFeatures {
+                null_move: false,
+                ..all
+            },
+        ),
+        (
+            "Without null move verification",
+            SearchFeatures {
+                null_move_verification: false,
+                ..all
+            },
+        ),
+        (
+            "Without late move reductions",
+            SearchFeatures {
+                late_move_reductions: false,
+                ..all
+            },
+        ),
+        (
+            "Without futility pruning",
+            SearchFeatures {
+                futility_pruning: false,
+                ..all
+            },
+        ),
+        (
+            "Without reverse futility pruning",
+            SearchFeatures {
+                reverse_futility_pruning: false,
+                ..all
+            },
+        ),
+        (
+            "Without principal variation search",
+            SearchFeatures { pvs: false, ..all },
+        ),
+    ];
+    for (name, features) in configurations.iter() {
+        let tt = TranspositionTable::new(DEFAULT_TT_SIZE_MB);
+        let mut search = Search::new(&tt).with_features(*features);
 
-    let time = t2 - t1;
-    println!(
-        "Depth {} searched in {}s {}ms",
-        depth,
-        time.num_seconds(),
-        time.num_milliseconds() % 1000
-    );
+        let t1 = time::get_time();
+        let result = search.iterative_deepening(&board, depth as usize);
+        let t2 = time::get_time();
+
+        let time = t2 - t1;
+        println!(
+            "{}: depth {} searched in {}s {}ms, {} nodes, score {}, best move {}",
+            name,
+            depth,
+            time.num_seconds(),
+            time.num_milliseconds() % 1000,
+            search.nodes(),
+            result.score,
+            result
+                .best_move()
+                .map_or_else(|| "none".to_string(), |m| m.to_string())
+        );
+    }
 }
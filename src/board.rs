@@ -2,11 +2,13 @@ use std::fmt::Display;
 
 use bitintr::*;
 
+use crate::attacks::{bishop_attacks, rook_attacks};
 use crate::board::Color::{Black, Empty, White};
 use crate::board::PieceKind::*;
 use crate::constants::*;
 use crate::flags::Flags;
 use crate::move_representation::Move;
+use crate::zobrist::{self, ZOBRIST};
 
 /// Bit overview of flags:
 /// 0: white to move
@@ -18,12 +20,33 @@ use crate::move_representation::Move;
 /// 8-15: en passant file (bit 8 set = file A en passant opportunity, bit 15 = file H)
 /// 16-31: halfmove_clock
 /// 32-47: fullmove_counter
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub struct Board {
     pub bitboard: BitBoard,
-    pub piece_positions_tzcnt: [u8; 32],
-    pub piece_kinds: [PieceKind; 32],
+    pub piece_positions_tzcnt: [u8; MAX_PIECES],
+    pub piece_kinds: [PieceKind; MAX_PIECES],
+    /// Entries of the piece list in use, captured pieces leave empty entries behind
+    piece_list_len: u8,
+    /// Piece list index of the piece on each square, NO_PIECE if empty
+    mailbox: [u8; 64],
+    flags: Flags,
+    zobrist: u64,
+}
+
+/// Mailbox entry of an empty square
+const NO_PIECE: u8 = u8::MAX;
+
+/// The piece list has room for a piece on every square
+pub const MAX_PIECES: usize = 64;
+
+/// State returned by make_move_in_place that can't be recovered from the move itself, used by
+/// unmake_move to restore the board
+#[derive(Copy, Clone, Debug)]
+pub struct Undo {
     flags: Flags,
+    zobrist: u64,
+    captured_kind: PieceKind,
+    captured_piecelist_i: usize,
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -112,6 +135,32 @@ impl BitBoard {
         self.black_king &= !capture_pos;
     }
 
+    /// Toggle the given squares in the bitboards of the piece kind
+    #[inline]
+    pub fn toggle_piece(&mut self, kind: PieceKind, bits: u64) {
+        match kind {
+            WhiteQueen => {
+                self.white_rooklike ^= bits;
+                self.white_bishoplike ^= bits;
+            }
+            WhiteKing => self.white_king ^= bits,
+            WhiteRook => self.white_rooklike ^= bits,
+            WhiteBishop => self.white_bishoplike ^= bits,
+            WhiteKnight => self.white_knights ^= bits,
+            WhitePawn => self.white_pawns ^= bits,
+            BlackQueen => {
+                self.black_rooklike ^= bits;
+                self.black_bishoplike ^= bits;
+            }
+            BlackKing => self.black_king ^= bits,
+            BlackRook => self.black_rooklike ^= bits,
+            BlackBishop => self.black_bishoplike ^= bits,
+            BlackKnight => self.black_knights ^= bits,
+            BlackPawn => self.black_pawns ^= bits,
+            EmptySquare => unreachable!(),
+        }
+    }
+
     #[inline]
     pub fn white_queen_coverage(&self) -> u64 {
         self.white_bishoplike & self.white_rooklike
@@ -198,8 +247,8 @@ impl Board {
     #[allow(clippy::too_many_arguments)]
     pub fn new(
         bitboard: BitBoard,
-        piece_positions_tzcnt: [u8; 32],
-        piece_kinds: [PieceKind; 32],
+        piece_positions_tzcnt: [u8; MAX_PIECES],
+        piece_kinds: [PieceKind; MAX_PIECES],
         halfmove_clock: u16,
         fullmove_counter: u16,
         en_passant: u64,
@@ -210,9 +259,19 @@ impl Board {
             bitboard,
             piece_positions_tzcnt,
             piece_kinds,
+            piece_list_len: 0,
+            mailbox: [NO_PIECE; 64],
             flags: Flags(0),
+            zobrist: 0,
         };
 
+        for (i, pos_tzcnt) in piece_positions_tzcnt.iter().enumerate() {
+            if *pos_tzcnt != TZCNT_U64_ZEROS {
+                board.mailbox[*pos_tzcnt as usize] = i as u8;
+                board.piece_list_len = i as u8 + 1;
+            }
+        }
+
         board.flags.set_bit(0, white_to_move);
         for i in 0..4 {
             if (castling_availability & (1 << i)) != 0 {
@@ -226,6 +285,7 @@ impl Board {
             let file = (pos_to_file_index(en_passant) + 1) as u8;
             board.set_en_passant(file);
         }
+        board.zobrist = board.compute_zobrist();
 
         board
     }
@@ -234,14 +294,34 @@ impl Board {
         println!("{}", self);
     }
 
-    pub fn slow_kind_at(&self, pos: u64) -> PieceKind {
-        let pos_tzcnt = pos.tzcnt() as u8;
-        for (i, ptz) in self.piece_positions_tzcnt.iter().enumerate() {
-            if *ptz == pos_tzcnt {
-                return self.piece_kinds[i];
-            }
+    /// Piece list entries beyond this are always empty
+    #[inline]
+    pub fn piece_list_len(&self) -> usize {
+        self.piece_list_len as usize
+    }
+
+    #[inline]
+    pub fn kind_at(&self, pos_tzcnt: u8) -> PieceKind {
+        match self.mailbox[pos_tzcnt as usize] {
+            NO_PIECE => EmptySquare,
+            i => self.piece_kinds[i as usize],
         }
-        EmptySquare
+    }
+
+    /// Piece list index of the piece on the square, which must be occupied
+    #[inline]
+    fn piecelist_index_at(&self, pos_tzcnt: u8) -> usize {
+        let i = self.mailbox[pos_tzcnt as usize];
+        debug_assert_ne!(i, NO_PIECE, "No piece on square {}", pos_tzcnt);
+        i as usize
+    }
+
+    /// Move a piece list entry between squares, keeping the mailbox in sync
+    #[inline]
+    fn move_piecelist_entry(&mut self, piecelist_i: usize, from_tzcnt: u8, to_tzcnt: u8) {
+        self.piece_positions_tzcnt[piecelist_i] = to_tzcnt;
+        self.mailbox[from_tzcnt as usize] = NO_PIECE;
+        self.mailbox[to_tzcnt as usize] = piecelist_i as u8;
     }
 
     #[inline]
@@ -308,6 +388,32 @@ impl Board {
         self.flags.get_bit(1)
     }
 
+    /// Castling availability as 0b0000KQkq
+    #[inline]
+    pub fn castling_bits(&self) -> u8 {
+        ((self.flags.0 >> 1) & 0xF) as u8
+    }
+
+    /// Zobrist key of the position, maintained incrementally by make_move_in_place
+    #[inline]
+    pub fn zobrist(&self) -> u64 {
+        self.zobrist
+    }
+
+    #[inline]
+    fn zobrist_state_key(&self) -> u64 {
+        zobrist::state_key(
+            self.white_to_move(),
+            self.castling_bits(),
+            self.get_en_passant_file(),
+        )
+    }
+
+    #[inline]
+    fn toggle_zobrist_piece(&mut self, kind: PieceKind, pos_tzcnt: u8) {
+        self.zobrist ^= ZOBRIST.pieces[kind as usize][pos_tzcnt as usize];
+    }
+
     pub fn get_halfmove_clock(&self) -> u16 {
         ((self.flags.0 >> 16) & (0xFFFF_u64)) as u16
     }
@@ -477,8 +583,16 @@ impl Board {
         new
     }
 
-    pub fn make_move_in_place(&mut self, mov: &Move) {
+    pub fn make_move_in_place(&mut self, mov: &Move) -> Undo {
+        let mut undo = Undo {
+            flags: self.flags,
+            zobrist: self.zobrist,
+            captured_kind: EmptySquare,
+            captured_piecelist_i: 0,
+        };
         let white = self.white_to_move();
+        // Remove side to move, castling and en passant from the key, added back after the move
+        self.zobrist ^= self.zobrist_state_key();
         if !white {
             self.increment_fullmove_counter();
         }
@@ -489,9 +603,8 @@ impl Board {
         let to_tzcnt = mov.to();
         let from: u64 = 1 << from_tzcnt;
         let to: u64 = 1 << to_tzcnt;
-        let from_piecelist_i = self.slow_get_piecelist_index_of_pos(from_tzcnt);
+        let from_piecelist_i = self.piecelist_index_at(from_tzcnt);
         let from_kind = self.piece_kinds[from_piecelist_i];
-        let toggle_bits = from | to;
 
         let flags = mov.flags_nibble();
         let is_promotion = (flags & 0b1000) != 0;
@@ -505,6 +618,9 @@ impl Board {
         self.castling_maintenance(from_tzcnt, to_tzcnt);
 
         if is_promotion {
+            if is_capture {
+                self.capture(to_tzcnt, &mut undo);
+            }
             if white {
                 self.bitboard.white_pawns ^= from;
                 match flags & 0b11 {
@@ -528,7 +644,6 @@ impl Board {
                     _ => unreachable!(),
                 }
                 if is_capture {
-                    self.delete_from_piecelist(to_tzcnt);
                     self.bitboard.unset_black_piece(to);
                 }
             } else {
@@ -554,46 +669,144 @@ impl Board {
                     _ => unreachable!(),
                 }
                 if is_capture {
-                    self.delete_from_piecelist(to_tzcnt);
                     self.bitboard.unset_white_piece(to);
                 }
             }
-            self.piece_positions_tzcnt[from_piecelist_i] = to_tzcnt;
+            self.move_piecelist_entry(from_piecelist_i, from_tzcnt, to_tzcnt);
+            self.toggle_zobrist_piece(from_kind, from_tzcnt);
+            self.toggle_zobrist_piece(self.piece_kinds[from_piecelist_i], to_tzcnt);
+        } else {
+            self.make_non_promotion_move(mov, white, from_piecelist_i, from_kind, &mut undo);
+        }
+
+        self.zobrist ^= self.zobrist_state_key();
+        debug_assert_eq!(
+            self.zobrist,
+            self.compute_zobrist(),
+            "Incremental zobrist key diverged after {}",
+            mov
+        );
+        undo
+    }
+
+    /// Take back a move made by make_move_in_place, given the undo record it returned
+    pub fn unmake_move(&mut self, mov: &Move, undo: &Undo) {
+        self.flags = undo.flags;
+        self.zobrist = undo.zobrist;
+        let white = self.white_to_move();
+
+        let from_tzcnt = mov.from();
+        let to_tzcnt = mov.to();
+        let from: u64 = 1 << from_tzcnt;
+        let to: u64 = 1 << to_tzcnt;
+        let piecelist_i = self.piecelist_index_at(to_tzcnt);
+        let kind = self.piece_kinds[piecelist_i];
+        let flags = mov.flags_nibble();
 
-            return;
+        if mov.is_promotion() {
+            let pawn = if white { WhitePawn } else { BlackPawn };
+            self.bitboard.toggle_piece(kind, to);
+            self.bitboard.toggle_piece(pawn, from);
+            self.piece_kinds[piecelist_i] = pawn;
+        } else {
+            self.bitboard.toggle_piece(kind, from | to);
+            if flags == 0b0010 || flags == 0b0011 {
+                // Castling, move the rook back
+                let (rook_from_tzcnt, rook_to_tzcnt) = match to_tzcnt {
+                    6 => (7, 5),
+                    2 => (0, 3),
+                    62 => (63, 61),
+                    58 => (56, 59),
+                    _ => unreachable!(),
+                };
+                let rook = if white { WhiteRook } else { BlackRook };
+                self.bitboard
+                    .toggle_piece(rook, (1 << rook_from_tzcnt) | (1 << rook_to_tzcnt));
+                let rook_piecelist_i = self.piecelist_index_at(rook_to_tzcnt);
+                self.move_piecelist_entry(rook_piecelist_i, rook_to_tzcnt, rook_from_tzcnt);
+            }
         }
+        self.move_piecelist_entry(piecelist_i, to_tzcnt, from_tzcnt);
 
-        match flags & 0b111 {
-            0b000 => {
-                // "Normal" move
+        if undo.captured_kind != EmptySquare {
+            let captured_pos_tzcnt = if flags == 0b0101 {
+                // En passant, the captured pawn is behind the target square
                 if white {
-                    match from_kind {
-                        WhiteQueen => {
-                            self.bitboard.white_rooklike ^= toggle_bits;
-                            self.bitboard.white_bishoplike ^= toggle_bits;
-                        }
-                        WhiteKing => self.bitboard.white_king ^= toggle_bits,
-                        WhiteRook => self.bitboard.white_rooklike ^= toggle_bits,
-                        WhiteBishop => self.bitboard.white_bishoplike ^= toggle_bits,
-                        WhiteKnight => self.bitboard.white_knights ^= toggle_bits,
-                        WhitePawn => self.bitboard.white_pawns ^= toggle_bits,
-                        _ => unreachable!(),
-                    }
+                    to_tzcnt - 8
                 } else {
-                    match from_kind {
-                        BlackQueen => {
-                            self.bitboard.black_rooklike ^= toggle_bits;
-                            self.bitboard.black_bishoplike ^= toggle_bits;
-                        }
-                        BlackKing => self.bitboard.black_king ^= toggle_bits,
-                        BlackRook => self.bitboard.black_rooklike ^= toggle_bits,
-                        BlackBishop => self.bitboard.black_bishoplike ^= toggle_bits,
-                        BlackKnight => self.bitboard.black_knights ^= toggle_bits,
-                        BlackPawn => self.bitboard.black_pawns ^= toggle_bits,
-                        _ => unreachable!(),
-                    }
+                    to_tzcnt + 8
                 }
-                self.piece_positions_tzcnt[from_piecelist_i] = to_tzcnt;
+            } else {
+                to_tzcnt
+            };
+            self.piece_positions_tzcnt[undo.captured_piecelist_i] = captured_pos_tzcnt;
+            self.piece_kinds[undo.captured_piecelist_i] = undo.captured_kind;
+            self.mailbox[captured_pos_tzcnt as usize] = undo.captured_piecelist_i as u8;
+            self.bitboard
+                .toggle_piece(undo.captured_kind, 1 << captured_pos_tzcnt);
+        }
+    }
+
+    /// Pass the turn without moving, for null move pruning https://www.chessprogramming.org/Null_Move
+    pub fn make_null_move(&mut self) -> Undo {
+        let undo = Undo {
+            flags: self.flags,
+            zobrist: self.zobrist,
+            captured_kind: EmptySquare,
+            captured_piecelist_i: 0,
+        };
+        self.zobrist ^= self.zobrist_state_key();
+        if !self.white_to_move() {
+            self.increment_fullmove_counter();
+        }
+        self.reset_en_passant();
+        self.toggle_side_to_move();
+        self.zobrist ^= self.zobrist_state_key();
+        undo
+    }
+
+    pub fn unmake_null_move(&mut self, undo: &Undo) {
+        self.flags = undo.flags;
+        self.zobrist = undo.zobrist;
+    }
+
+    /// Remove a captured piece from the piece list and key, remembering it in the undo record.
+    /// The caller updates the bitboards.
+    #[inline]
+    fn capture(&mut self, pos_tzcnt: u8, undo: &mut Undo) {
+        let piecelist_i = self.piecelist_index_at(pos_tzcnt);
+        let kind = self.piece_kinds[piecelist_i];
+        self.piece_positions_tzcnt[piecelist_i] = TZCNT_U64_ZEROS;
+        self.piece_kinds[piecelist_i] = EmptySquare;
+        self.mailbox[pos_tzcnt as usize] = NO_PIECE;
+        self.toggle_zobrist_piece(kind, pos_tzcnt);
+        undo.captured_kind = kind;
+        undo.captured_piecelist_i = piecelist_i;
+    }
+
+    #[inline]
+    fn make_non_promotion_move(
+        &mut self,
+        mov: &Move,
+        white: bool,
+        from_piecelist_i: usize,
+        from_kind: PieceKind,
+        undo: &mut Undo,
+    ) {
+        let from_tzcnt = mov.from();
+        let to_tzcnt = mov.to();
+        let from: u64 = 1 << from_tzcnt;
+        let to: u64 = 1 << to_tzcnt;
+        let toggle_bits = from | to;
+
+        self.toggle_zobrist_piece(from_kind, from_tzcnt);
+        self.toggle_zobrist_piece(from_kind, to_tzcnt);
+
+        match mov.flags_nibble() & 0b111 {
+            0b000 => {
+                // "Normal" move
+                self.bitboard.toggle_piece(from_kind, toggle_bits);
+                self.move_piecelist_entry(from_piecelist_i, from_tzcnt, to_tzcnt);
             }
             0b001 => {
                 // Double pawn push
@@ -606,76 +819,58 @@ impl Board {
                     let ep_file = (pos_to_file_index(from >> 8) + 1) as u8;
                     self.set_en_passant(ep_file);
                 }
-                self.piece_positions_tzcnt[from_piecelist_i] = to_tzcnt;
+                self.move_piecelist_entry(from_piecelist_i, from_tzcnt, to_tzcnt);
             }
             0b010 => {
                 // Kingside castle
                 if white {
                     self.bitboard.white_king = 1 << 6;
                     self.bitboard.white_rooklike ^= (1 << 7) | (1 << 5);
-                    let piecelist_rook_i = self.slow_get_piecelist_index_of_pos(7);
-                    self.piece_positions_tzcnt[piecelist_rook_i] = 5;
+                    let piecelist_rook_i = self.piecelist_index_at(7);
+                    self.move_piecelist_entry(piecelist_rook_i, 7, 5);
+                    self.toggle_zobrist_piece(WhiteRook, 7);
+                    self.toggle_zobrist_piece(WhiteRook, 5);
                 } else {
                     self.bitboard.black_king = 1 << 62;
                     self.bitboard.black_rooklike ^= (1 << 61) | (1 << 63);
-                    let piecelist_rook_i = self.slow_get_piecelist_index_of_pos(63);
-                    self.piece_positions_tzcnt[piecelist_rook_i] = 61;
+                    let piecelist_rook_i = self.piecelist_index_at(63);
+                    self.move_piecelist_entry(piecelist_rook_i, 63, 61);
+                    self.toggle_zobrist_piece(BlackRook, 63);
+                    self.toggle_zobrist_piece(BlackRook, 61);
                 }
-                self.piece_positions_tzcnt[from_piecelist_i] = to_tzcnt;
+                self.move_piecelist_entry(from_piecelist_i, from_tzcnt, to_tzcnt);
             }
             0b011 => {
                 // Queenside castle
                 if white {
                     self.bitboard.white_king = 1 << 2;
                     self.bitboard.white_rooklike ^= 1 | (1 << 3);
-                    let piecelist_rook_i = self.slow_get_piecelist_index_of_pos(0);
-                    self.piece_positions_tzcnt[piecelist_rook_i] = 3;
+                    let piecelist_rook_i = self.piecelist_index_at(0);
+                    self.move_piecelist_entry(piecelist_rook_i, 0, 3);
+                    self.toggle_zobrist_piece(WhiteRook, 0);
+                    self.toggle_zobrist_piece(WhiteRook, 3);
                 } else {
                     self.bitboard.black_king = 1 << 58;
                     self.bitboard.black_rooklike ^= (1 << 56) | (1 << 59);
-                    let piecelist_rook_i = self.slow_get_piecelist_index_of_pos(56);
-                    self.piece_positions_tzcnt[piecelist_rook_i] = 59;
+                    let piecelist_rook_i = self.piecelist_index_at(56);
+                    self.move_piecelist_entry(piecelist_rook_i, 56, 59);
+                    self.toggle_zobrist_piece(BlackRook, 56);
+                    self.toggle_zobrist_piece(BlackRook, 59);
                 }
-                self.piece_positions_tzcnt[from_piecelist_i] = to_tzcnt;
+                self.move_piecelist_entry(from_piecelist_i, from_tzcnt, to_tzcnt);
             }
             0b100 => {
                 // "Normal" capture
-                if white {
-                    match from_kind {
-                        WhiteQueen => {
-                            self.bitboard.white_rooklike ^= toggle_bits;
-                            self.bitboard.white_bishoplike ^= toggle_bits;
-                        }
-                        WhiteKing => self.bitboard.white_king ^= toggle_bits,
-                        WhiteRook => self.bitboard.white_rooklike ^= toggle_bits,
-                        WhiteBishop => self.bitboard.white_bishoplike ^= toggle_bits,
-                        WhiteKnight => self.bitboard.white_knights ^= toggle_bits,
-                        WhitePawn => self.bitboard.white_pawns ^= toggle_bits,
-                        _ => unreachable!(),
-                    }
-                } else {
-                    match from_kind {
-                        BlackQueen => {
-                            self.bitboard.black_rooklike ^= toggle_bits;
-                            self.bitboard.black_bishoplike ^= toggle_bits;
-                        }
-                        BlackKing => self.bitboard.black_king ^= toggle_bits,
-                        BlackRook => self.bitboard.black_rooklike ^= toggle_bits,
-                        BlackBishop => self.bitboard.black_bishoplike ^= toggle_bits,
-                        BlackKnight => self.bitboard.black_knights ^= toggle_bits,
-                        BlackPawn => self.bitboard.black_pawns ^= toggle_bits,
-                        _ => unreachable!(),
-                    }
-                }
+                self.bitboard.toggle_piece(from_kind, toggle_bits);
 
-                self.delete_from_piecelist(to_tzcnt);
+                self.capture(to_tzcnt, undo);
                 if white {
                     self.bitboard.unset_black_piece(to);
                 } else {
                     self.bitboard.unset_white_piece(to);
                 }
 
-                self.piece_positions_tzcnt[from_piecelist_i] = to_tzcnt;
+                self.move_piecelist_entry(from_piecelist_i, from_tzcnt, to_tzcnt);
             }
             0b101 => {
                 // Ep capture
@@ -689,34 +884,13 @@ impl Board {
                     self.bitboard.black_pawns ^= toggle_bits;
                     self.bitboard.white_pawns ^= opponent_square;
                 }
-                self.piece_positions_tzcnt[from_piecelist_i] = to_tzcnt;
-                self.delete_from_piecelist(opponent_square.tzcnt() as u8);
+                self.move_piecelist_entry(from_piecelist_i, from_tzcnt, to_tzcnt);
+                self.capture(opponent_square.tzcnt() as u8, undo);
             }
             _ => unreachable!(),
         }
     }
 
-    pub fn slow_get_piecelist_index_of_pos(&self, pos_tzcnt: u8) -> usize {
-        let (piece_index, _) = self
-            .piece_positions_tzcnt
-            .iter()
-            .enumerate()
-            .find(|(_, p)| **p == pos_tzcnt)
-            .unwrap();
-        piece_index
-    }
-
-    pub fn delete_from_piecelist(&mut self, capture_pos_tzcnt: u8) {
-        let piece_positions_tzcnt = &mut self.piece_positions_tzcnt;
-        for (i, p) in piece_positions_tzcnt.iter().enumerate() {
-            if *p == capture_pos_tzcnt {
-                piece_positions_tzcnt[i] = TZCNT_U64_ZEROS;
-                self.piece_kinds[i] = EmptySquare;
-                break;
-            }
-        }
-    }
-
     #[inline]
     fn castling_maintenance(&mut self, pos_from_tzcnt: u8, pos_to_tzcnt: u8) {
         match pos_from_tzcnt {
@@ -767,8 +941,8 @@ impl Default for Board {
         ps[30] = (BlackRook, ROW_8 & FILE_A);
         ps[31] = (BlackRook, ROW_8 & FILE_H);
 
-        let mut piece_positions = [TZCNT_U64_ZEROS; 32];
-        let mut piece_kinds = [PieceKind::EmptySquare; 32];
+        let mut piece_positions = [TZCNT_U64_ZEROS; MAX_PIECES];
+        let mut piece_kinds = [PieceKind::EmptySquare; MAX_PIECES];
 
         for i in 0..32 {
             piece_kinds[i] = ps[i].0;
@@ -810,7 +984,7 @@ impl Display for Board {
 
             for file in &FILES {
                 let pos = row & file;
-                let piece = self.slow_kind_at(pos);
+                let piece = self.kind_at(pos.tzcnt() as u8);
                 write!(f, "{} ", piece)?;
             }
 
@@ -827,119 +1001,17 @@ fn is_attacked_by_knight(position: u64, enemy_knights: u64) -> bool {
 }
 
 fn is_attacked_by_rooklike(position: u64, enemy_rooklike: u64, total_occupancy: u64) -> bool {
-    if !intersects(position, FILE_H) {
-        let mut target_pos = position << 1;
-        loop {
-            if intersects(target_pos, enemy_rooklike) {
-                return true;
-            }
-
-            if intersects(target_pos, total_occupancy | FILE_H) {
-                break;
-            }
-            target_pos <<= 1;
-        }
-    }
-    if !intersects(position, FILE_A) {
-        let mut target_pos = position >> 1;
-        loop {
-            if intersects(target_pos, enemy_rooklike) {
-                return true;
-            }
-
-            if intersects(target_pos, total_occupancy | FILE_A) {
-                break;
-            }
-            target_pos >>= 1;
-        }
-    }
-    if !intersects(position, ROW_8) {
-        let mut target_pos = position << 8;
-        loop {
-            if intersects(target_pos, enemy_rooklike) {
-                return true;
-            }
-
-            if intersects(target_pos, total_occupancy | ROW_8) {
-                break;
-            }
-            target_pos <<= 8;
-        }
-    }
-    if !intersects(position, ROW_1) {
-        let mut target_pos = position >> 8;
-        loop {
-            if intersects(target_pos, enemy_rooklike) {
-                return true;
-            }
-
-            if intersects(target_pos, total_occupancy | ROW_1) {
-                break;
-            }
-            target_pos >>= 8;
-        }
-    }
-    false
+    intersects(
+        rook_attacks(position.tzcnt() as u8, total_occupancy),
+        enemy_rooklike,
+    )
 }
 
 fn is_attacked_by_bishoplike(position: u64, enemy_bishoplike: u64, total_occupancy: u64) -> bool {
-    let top = intersects(position, ROW_8);
-    let bottom = intersects(position, ROW_1);
-    let right = intersects(position, FILE_H);
-    let left = intersects(position, FILE_A);
-
-    if !top && !right {
-        let mut target_pos = position << 9;
-        loop {
-            if intersects(target_pos, enemy_bishoplike) {
-                return true;
-            }
-            if intersects(target_pos, total_occupancy | ROW_8 | FILE_H) {
-                break;
-            }
-            target_pos <<= 9;
-        }
-    }
-
-    if !top && !left {
-        let mut target_pos = position << 7;
-        loop {
-            if intersects(target_pos, enemy_bishoplike) {
-                return true;
-            }
-            if intersects(target_pos, total_occupancy | ROW_8 | FILE_A) {
-                break;
-            }
-            target_pos <<= 7;
-        }
-    }
-
-    if !bottom && !right {
-        let mut target_pos = position >> 7;
-        loop {
-            if intersects(target_pos, enemy_bishoplike) {
-                return true;
-            }
-            if intersects(target_pos, total_occupancy | ROW_1 | FILE_H) {
-                break;
-            }
-            target_pos >>= 7;
-        }
-    }
-
-    if !bottom && !left {
-        let mut target_pos = position >> 9;
-        loop {
-            if intersects(target_pos, enemy_bishoplike) {
-                return true;
-            }
-            if intersects(target_pos, total_occupancy | ROW_1 | FILE_A) {
-                break;
-            }
-            target_pos >>= 9;
-        }
-    }
-    false
+    intersects(
+        bishop_attacks(position.tzcnt() as u8, total_occupancy),
+        enemy_bishoplike,
+    )
 }
 
 fn is_attacked_by_white_pawns(position: u64, white_pawns: u64) -> bool {
@@ -24,6 +24,10 @@ pub const FILES: [u64; 8] = [
     FILE_A, FILE_B, FILE_C, FILE_D, FILE_E, FILE_F, FILE_G, FILE_H,
 ];
 
+/// b1, d1 ... a2, c2 ... g8
+pub const LIGHT_SQUARES: u64 = 0x55AA55AA55AA55AA;
+pub const DARK_SQUARES: u64 = !LIGHT_SQUARES;
+
 pub const EMPTY_SQUARE: u8 = 0;
 
 pub const WHITE_PAWN: u8 = 1;
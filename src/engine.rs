@@ -92,10 +92,93 @@ impl Board {
         }
     }
 
+    #[inline]
+    fn piece_captures(
+        &self,
+        piece_index: usize,
+        our_occupancy: u64,
+        enemy_occupancy: u64,
+        white_to_move: bool,
+        outvec: &mut Vec<Move>,
+    ) {
+        let piece_position_tzcnt = self.piece_positions_tzcnt[piece_index];
+        if piece_position_tzcnt == TZCNT_U64_ZEROS {
+            // No position => empty square
+            return;
+        }
+        let piece_kind = self.piece_kinds[piece_index];
+
+        if white_to_move ^ piece_kind.is_white() {
+            return;
+        }
+
+        let total_occupancy = our_occupancy | enemy_occupancy;
+        match piece_kind {
+            WhitePawn => {
+                white_pawn_captures(
+                    self,
+                    piece_position_tzcnt,
+                    our_occupancy,
+                    enemy_occupancy,
+                    outvec,
+                );
+            }
+            BlackPawn => {
+                black_pawn_captures(
+                    self,
+                    piece_position_tzcnt,
+                    our_occupancy,
+                    enemy_occupancy,
+                    outvec,
+                );
+            }
+            WhiteRook | BlackRook => {
+                rooklike_captures(
+                    piece_position_tzcnt,
+                    total_occupancy,
+                    enemy_occupancy,
+                    outvec,
+                );
+            }
+            WhiteKnight | BlackKnight => {
+                knight_captures(piece_position_tzcnt, enemy_occupancy, outvec);
+            }
+            WhiteKing | BlackKing => {
+                king_captures(piece_position_tzcnt, enemy_occupancy, outvec);
+            }
+            WhiteQueen | BlackQueen => {
+                rooklike_captures(
+                    piece_position_tzcnt,
+                    total_occupancy,
+                    enemy_occupancy,
+                    outvec,
+                );
+                bishoplike_captures(
+                    piece_position_tzcnt,
+                    total_occupancy,
+                    enemy_occupancy,
+                    outvec,
+                );
+            }
+            WhiteBishop | BlackBishop => {
+                bishoplike_captures(
+                    piece_position_tzcnt,
+                    total_occupancy,
+                    enemy_occupancy,
+                    outvec,
+                );
+            }
+
+            EmptySquare => {
+                unreachable!()
+            }
+        }
+    }
+
     pub fn generate_pseudo_legal_moves(&self) -> Vec<Move> {
         let (our_occupancy, enemy_occupancy) = self.split_occupancy();
         let mut moves = Vec::with_capacity(64);
-        for i in 0..32 {
+        for i in 0..self.piece_list_len() {
             self.piece_moves(
                 i,
                 our_occupancy,
@@ -108,6 +191,23 @@ impl Board {
         moves
     }
 
+    /// Captures and promotions only, as searched by quiescence search
+    pub fn generate_pseudo_legal_captures(&self) -> Vec<Move> {
+        let (our_occupancy, enemy_occupancy) = self.split_occupancy();
+        let mut moves = Vec::with_capacity(16);
+        for i in 0..self.piece_list_len() {
+            self.piece_captures(
+                i,
+                our_occupancy,
+                enemy_occupancy,
+                self.white_to_move(),
+                &mut moves,
+            );
+        }
+
+        moves
+    }
+
     pub fn generate_successors(&self) -> Vec<Board> {
         let moves = self.generate_pseudo_legal_moves();
         let mut states = Vec::with_capacity(moves.len());
@@ -143,7 +243,7 @@ impl<'a> Iterator for SuccessorIter<'a> {
         }
 
         let piece_kinds = &self.board.piece_kinds;
-        while self.piece_index < 32 {
+        while self.piece_index < self.board.piece_list_len() {
             let piece_kind = piece_kinds[self.piece_index];
             if piece_kind == EmptySquare {
                 self.piece_index += 1;
@@ -1,10 +1,35 @@
 use bitintr::{Popcnt, Tzcnt};
 
-use crate::board::Board;
+use crate::board::PieceKind::*;
+use crate::board::{Board, PieceKind};
 
 pub const INF: i32 = i32::MAX;
 pub const NEGINF: i32 = i32::MIN + 1;
 
+/// Deepest ply the search will reach from the root
+pub const MAX_PLY: usize = 128;
+/// Score for mating at the root, mate at ply n scores MATE - n (and -MATE + n when mated)
+pub const MATE: i32 = INF - 1;
+
+#[inline]
+pub fn is_mate_score(score: i32) -> bool {
+    score.abs() >= MATE - MAX_PLY as i32
+}
+
+/// Full moves until mate for a score from the side to move's perspective, negative when being
+/// mated. None for scores that are not mate scores.
+pub fn mate_in_moves(score: i32) -> Option<i32> {
+    if !is_mate_score(score) {
+        return None;
+    }
+    let plies = MATE - score.abs();
+    if score > 0 {
+        Some((plies + 1) / 2)
+    } else {
+        Some(-plies / 2)
+    }
+}
+
 pub const PAWN: i32 = 100;
 pub const KNIGHT: i32 = 320;
 pub const BISHOP: i32 = 330;
@@ -102,12 +127,25 @@ pub const BLACK_KING_TABLE_ENDGAME: [i32; 64] = [
     -30, -50,
 ];
 
+/// Material value of a piece kind, en passant captures of an EmptySquare count as pawns
+pub fn piece_value(kind: PieceKind) -> i32 {
+    match kind {
+        WhitePawn | BlackPawn | EmptySquare => PAWN,
+        WhiteKnight | BlackKnight => KNIGHT,
+        WhiteBishop | BlackBishop => BISHOP,
+        WhiteRook | BlackRook => ROOK,
+        WhiteQueen | BlackQueen => QUEEN,
+        WhiteKing | BlackKing => KING,
+    }
+}
+
 #[inline]
 fn count(bits: u64) -> i32 {
     bits.popcnt() as i32
 }
 
 impl Board {
+    /// Static evaluation from white's perspective
     pub fn eval(&self) -> i32 {
         let king = self.king_eval_diff();
         if king != 0 {
@@ -117,6 +155,16 @@ impl Board {
         self.piece_eval_diff()
     }
 
+    /// Evaluation from the side to move's perspective, as the search uses it
+    #[inline]
+    pub fn relative_eval(&self) -> i32 {
+        if self.white_to_move() {
+            self.eval()
+        } else {
+            -self.eval()
+        }
+    }
+
     // Loosely based on https://www.chessprogramming.org/Simplified_Evaluation_Function
     fn piece_eval_diff(&self) -> i32 {
         let bb = self.bitboard;
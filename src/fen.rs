@@ -1,71 +1,134 @@
+use std::fmt;
+use std::str::FromStr;
+
 use bitintr::*;
 
 use crate::board::PieceKind::*;
-use crate::board::{BitBoard, Board, PieceKind};
+use crate::board::{BitBoard, Board, PieceKind, MAX_PIECES};
 use crate::constants::*;
+use crate::validation::PositionViolation;
 
 pub const FEN_DEFAULT_BOARD: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
 
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FenField {
+    PiecePlacement,
+    SideToMove,
+    Castling,
+    EnPassant,
+    HalfmoveClock,
+    FullmoveCounter,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FenError {
+    /// A FEN has 4 fields, or 6 with the move clocks
+    FieldCount(usize),
+    /// The field is malformed, offset is the character offset into the FEN of the first
+    /// character that could not be parsed
+    InvalidField {
+        field: FenField,
+        offset: usize,
+        reason: &'static str,
+    },
+    /// Well-formed, but not a position that can be played from
+    InsanePosition(Vec<PositionViolation>),
+}
+
+impl fmt::Display for FenField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            FenField::PiecePlacement => "piece placement",
+            FenField::SideToMove => "side to move",
+            FenField::Castling => "castling",
+            FenField::EnPassant => "en passant",
+            FenField::HalfmoveClock => "halfmove clock",
+            FenField::FullmoveCounter => "fullmove counter",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl fmt::Display for FenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FenError::FieldCount(n) => write!(f, "Expected 4 or 6 fields, found {}", n),
+            FenError::InvalidField {
+                field,
+                offset,
+                reason,
+            } => write!(f, "Invalid {} at character {}: {}", field, offset, reason),
+            FenError::InsanePosition(violations) => {
+                write!(f, "Not a playable position")?;
+                for (i, violation) in violations.iter().enumerate() {
+                    write!(f, "{} {}", if i == 0 { ":" } else { "," }, violation)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for FenError {}
+
+impl FromStr for Board {
+    type Err = FenError;
+
+    fn from_str(fen: &str) -> Result<Self, Self::Err> {
+        Board::from_fen(fen)
+    }
+}
+
 impl Board {
     /// Create Board from Forsyth–Edwards Notation
     /// https://en.wikipedia.org/wiki/Forsyth-Edwards_Notation
-    pub fn from_fen(fen: &str) -> Option<Board> {
-        let split: Vec<&str> = fen.split_whitespace().collect::<Vec<_>>();
-        if split.len() < 4 {
-            return None;
+    pub fn from_fen(fen: &str) -> Result<Board, FenError> {
+        // Fields with their character offsets
+        let fields: Vec<(usize, &str)> = fen
+            .split_whitespace()
+            .map(|field| {
+                let byte_offset = field.as_ptr() as usize - fen.as_ptr() as usize;
+                (fen[..byte_offset].chars().count(), field)
+            })
+            .collect();
+        if fields.len() != 4 && fields.len() != 6 {
+            return Err(FenError::FieldCount(fields.len()));
         }
-        let has_move_data = split.len() == 6;
 
-        let board = split.first().unwrap();
-        let (bitboard, pieces) = parse_board_string(board)?;
+        let (bitboard, pieces) = parse_board_string(fields[0].1, fields[0].0)?;
 
-        let (halfmove_clock, fullmove_counter) = if has_move_data {
+        let white_to_move = match fields[1].1 {
+            "w" => true,
+            "b" => false,
+            _ => {
+                return Err(invalid(
+                    FenField::SideToMove,
+                    fields[1].0,
+                    "expected w or b",
+                ))
+            }
+        };
+
+        let castling_availability = parse_castling(fields[2].1, fields[2].0)?;
+        let ep_pos = parse_en_passant(fields[3].1, fields[3].0, white_to_move)?;
+
+        let (halfmove_clock, fullmove_counter) = if fields.len() == 6 {
             (
-                split.get(4).unwrap().parse::<u16>().ok()?,
-                split.get(5).unwrap().parse::<u16>().ok()?,
+                parse_clock(fields[4], FenField::HalfmoveClock)?,
+                parse_clock(fields[5], FenField::FullmoveCounter)?,
             )
         } else {
             (0, 1)
         };
 
-        let white_to_move = split.get(1).unwrap() == &"w";
-
-        let mut piece_positions = [TZCNT_U64_ZEROS; 32];
-        let mut piece_kinds = [PieceKind::EmptySquare; 32];
+        let mut piece_positions = [TZCNT_U64_ZEROS; MAX_PIECES];
+        let mut piece_kinds = [PieceKind::EmptySquare; MAX_PIECES];
 
-        for i in 0..32 {
-            piece_kinds[i] = pieces[i].0;
-            let pos = pieces[i].1;
+        for (i, (kind, pos)) in pieces.iter().enumerate() {
+            piece_kinds[i] = *kind;
             piece_positions[i] = pos.tzcnt() as u8;
         }
 
-        let mut castling_availability = 0u8;
-        let castling_flags = split.get(2).unwrap();
-        for c in castling_flags.chars() {
-            match c {
-                'K' => castling_availability |= 1 << 3,
-                'Q' => castling_availability |= 1 << 2,
-                'k' => castling_availability |= 1 << 1,
-                'q' => castling_availability |= 1 << 0,
-                '-' => {
-                    castling_availability = 0;
-                    break;
-                }
-                _ => return None,
-            }
-        }
-
-        let ep_pos = {
-            let ep_field = split.get(3).unwrap();
-            let square_index = SQUARE_NAME.iter().position(|x| x == ep_field);
-
-            if let Some(i) = square_index {
-                1u64 << i
-            } else {
-                0
-            }
-        };
-
         let board = Board::new(
             bitboard,
             piece_positions,
@@ -77,21 +140,75 @@ impl Board {
             castling_availability,
         );
 
-        if board.is_sane_position() {
-            return Some(board);
+        let violations = board.validate();
+        if violations.iter().any(PositionViolation::is_unplayable) {
+            return Err(FenError::InsanePosition(violations));
         }
 
-        None
+        Ok(board)
     }
 
-    pub fn is_sane_position(&self) -> bool {
-        let bb = self.bitboard;
+    /// Write the board as six-field Forsyth–Edwards Notation
+    pub fn to_fen(&self) -> String {
+        let mut rows = Vec::with_capacity(8);
+        for row in (0..8u8).rev() {
+            let mut text = String::new();
+            let mut empty = 0;
+            for file in 0..8u8 {
+                match fen_char(self.kind_at(row * 8 + file)) {
+                    None => empty += 1,
+                    Some(c) => {
+                        if empty > 0 {
+                            text += &empty.to_string();
+                            empty = 0;
+                        }
+                        text.push(c);
+                    }
+                }
+            }
+            if empty > 0 {
+                text += &empty.to_string();
+            }
+            rows.push(text);
+        }
+
+        let side_to_move = if self.white_to_move() { "w" } else { "b" };
 
-        (bb.white_pawns.popcnt() <= 8)
-            && (bb.black_pawns.popcnt() <= 8)
-            && (bb.white_king.popcnt() == 1)
-            && (bb.black_king.popcnt() == 1)
-            && (((bb.white_pawns | bb.black_pawns) & (ROW_1 | ROW_8)) == 0)
+        let mut castling = String::new();
+        if self.can_white_castle_kingside() {
+            castling.push('K');
+        }
+        if self.can_white_castle_queenside() {
+            castling.push('Q');
+        }
+        if self.can_black_castle_kingside() {
+            castling.push('k');
+        }
+        if self.can_black_castle_queenside() {
+            castling.push('q');
+        }
+        if castling.is_empty() {
+            castling.push('-');
+        }
+
+        let en_passant = match self.get_en_passant_file() {
+            0 => "-",
+            file => {
+                // The square behind the pawn that just moved two squares
+                let row = if self.white_to_move() { 5 } else { 2 };
+                SQUARE_NAME[row * 8 + file as usize - 1]
+            }
+        };
+
+        format!(
+            "{} {} {} {} {} {}",
+            rows.join("/"),
+            side_to_move,
+            castling,
+            en_passant,
+            self.get_halfmove_clock(),
+            self.get_fullmove_counter()
+        )
     }
 }
 
@@ -114,60 +231,162 @@ fn fen_kind(piece: char) -> Option<PieceKind> {
     }
 }
 
-fn parse_board_string(board: &str) -> Option<(BitBoard, [(PieceKind, u64); 32])> {
-    let board_rows: Vec<&str> = board.split('/').collect::<Vec<_>>();
-    if board_rows.len() != 8 {
-        return None;
+/// Map kinds to FEN pieces, None for empty squares
+fn fen_char(kind: PieceKind) -> Option<char> {
+    match kind {
+        BlackRook => Some('r'),
+        BlackKnight => Some('n'),
+        BlackBishop => Some('b'),
+        BlackQueen => Some('q'),
+        BlackKing => Some('k'),
+        BlackPawn => Some('p'),
+        WhiteRook => Some('R'),
+        WhiteKnight => Some('N'),
+        WhiteBishop => Some('B'),
+        WhiteQueen => Some('Q'),
+        WhiteKing => Some('K'),
+        WhitePawn => Some('P'),
+        EmptySquare => None,
+    }
+}
+
+fn invalid(field: FenField, offset: usize, reason: &'static str) -> FenError {
+    FenError::InvalidField {
+        field,
+        offset,
+        reason,
     }
+}
+
+fn parse_board_string(
+    board: &str,
+    offset: usize,
+) -> Result<(BitBoard, Vec<(PieceKind, u64)>), FenError> {
+    let placement_error = |offset, reason| invalid(FenField::PiecePlacement, offset, reason);
 
     let mut bb = BitBoard::empty();
+    let mut pieces = Vec::with_capacity(32);
+
+    // Row 8 first
+    let mut row = 7;
+    let mut j = 0u64;
+    for (i, c) in board.chars().enumerate() {
+        let char_offset = offset + i;
+        if c == '/' {
+            if j != 8 {
+                return Err(placement_error(char_offset, "row does not have 8 squares"));
+            }
+            if row == 0 {
+                return Err(placement_error(char_offset, "more than 8 rows"));
+            }
+            row -= 1;
+            j = 0;
+            continue;
+        }
 
-    let mut pieces = [(EmptySquare, 0u64); 32]; // Limitation: only supports positions with <= 32 pieces
-    let mut piece_i: usize = 0;
+        if let Some(empty) = c.to_digit(10) {
+            if empty == 0 || empty > 8 - j as u32 {
+                return Err(placement_error(char_offset, "row does not have 8 squares"));
+            }
+            j += empty as u64;
+            continue;
+        }
 
-    for (i, pieces_str) in board_rows.iter().enumerate() {
-        let row = 7 - i;
-        let row_mask = ROWS[row];
+        let kind = fen_kind(c).ok_or_else(|| placement_error(char_offset, "unknown piece"))?;
+        if j >= 8 {
+            return Err(placement_error(char_offset, "row does not have 8 squares"));
+        }
+        let pos = ROWS[row] & (FILE_A << j);
 
-        let mut j = 0u64;
-        for c in pieces_str.chars() {
-            if c.is_ascii_digit() {
-                j += c.to_digit(10).unwrap() as u64;
-                continue;
+        match kind {
+            WhitePawn => bb.white_pawns ^= pos,
+            WhiteBishop => bb.white_bishoplike ^= pos,
+            WhiteKnight => bb.white_knights ^= pos,
+            WhiteRook => bb.white_rooklike ^= pos,
+            WhiteQueen => {
+                bb.white_rooklike ^= pos;
+                bb.white_bishoplike ^= pos;
             }
-            let kind = fen_kind(c)?;
-            let file_mask = FILE_A << j;
-            let pos = row_mask & file_mask;
-
-            match kind {
-                WhitePawn => bb.white_pawns ^= pos,
-                WhiteBishop => bb.white_bishoplike ^= pos,
-                WhiteKnight => bb.white_knights ^= pos,
-                WhiteRook => bb.white_rooklike ^= pos,
-                WhiteQueen => {
-                    bb.white_rooklike ^= pos;
-                    bb.white_bishoplike ^= pos;
-                }
-                WhiteKing => bb.white_king ^= pos,
-                BlackPawn => bb.black_pawns ^= pos,
-                BlackBishop => bb.black_bishoplike ^= pos,
-                BlackKnight => bb.black_knights ^= pos,
-                BlackRook => bb.black_rooklike ^= pos,
-                BlackQueen => {
-                    bb.black_rooklike ^= pos;
-                    bb.black_bishoplike ^= pos;
-                }
-                BlackKing => bb.black_king ^= pos,
-                _ => (),
-            };
+            WhiteKing => bb.white_king ^= pos,
+            BlackPawn => bb.black_pawns ^= pos,
+            BlackBishop => bb.black_bishoplike ^= pos,
+            BlackKnight => bb.black_knights ^= pos,
+            BlackRook => bb.black_rooklike ^= pos,
+            BlackQueen => {
+                bb.black_rooklike ^= pos;
+                bb.black_bishoplike ^= pos;
+            }
+            BlackKing => bb.black_king ^= pos,
+            EmptySquare => unreachable!(),
+        };
+
+        pieces.push((kind, pos));
+        j += 1;
+    }
 
-            if piece_i >= 32 {
-                return None;
+    let end = offset + board.chars().count();
+    if j != 8 {
+        return Err(placement_error(end, "row does not have 8 squares"));
+    }
+    if row != 0 {
+        return Err(placement_error(end, "fewer than 8 rows"));
+    }
+    Ok((bb, pieces))
+}
+
+/// Castling availability as 0b0000KQkq
+fn parse_castling(castling: &str, offset: usize) -> Result<u8, FenError> {
+    if castling == "-" {
+        return Ok(0);
+    }
+
+    let mut castling_availability = 0u8;
+    for (i, c) in castling.chars().enumerate() {
+        let bit = match c {
+            'K' => 1 << 3,
+            'Q' => 1 << 2,
+            'k' => 1 << 1,
+            'q' => 1 << 0,
+            _ => {
+                return Err(invalid(
+                    FenField::Castling,
+                    offset + i,
+                    "expected KQkq or -",
+                ))
             }
-            pieces[piece_i] = (kind, pos);
-            piece_i += 1;
-            j += 1;
+        };
+        if castling_availability & bit != 0 {
+            return Err(invalid(FenField::Castling, offset + i, "repeated right"));
         }
+        castling_availability |= bit;
+    }
+    Ok(castling_availability)
+}
+
+/// The en passant target square is behind a pawn of the side that just moved
+fn parse_en_passant(ep_field: &str, offset: usize, white_to_move: bool) -> Result<u64, FenError> {
+    if ep_field == "-" {
+        return Ok(0);
     }
-    Some((bb, pieces))
+
+    let square_index = SQUARE_NAME
+        .iter()
+        .position(|x| *x == ep_field)
+        .ok_or_else(|| invalid(FenField::EnPassant, offset, "expected a square or -"))?;
+    let ep_pos = 1u64 << square_index;
+    let expected_row = if white_to_move { ROW_6 } else { ROW_3 };
+    if !intersects(ep_pos, expected_row) {
+        return Err(invalid(
+            FenField::EnPassant,
+            offset,
+            "square is not behind a pawn that just moved two squares",
+        ));
+    }
+    Ok(ep_pos)
+}
+
+fn parse_clock((offset, clock): (usize, &str), field: FenField) -> Result<u16, FenError> {
+    clock
+        .parse::<u16>()
+        .map_err(|_| invalid(field, offset, "expected a number"))
 }
@@ -0,0 +1,156 @@
+use bitintr::Popcnt;
+
+use crate::board::{Board, Undo};
+use crate::constants::*;
+use crate::move_representation::Move;
+
+/// Halfmove clock at which the fifty-move rule ends the game
+pub const FIFTY_MOVE_HALFMOVES: u16 = 100;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GameResult {
+    Ongoing,
+    /// The side to move is mated
+    Checkmate {
+        white_wins: bool,
+    },
+    Stalemate,
+    /// The position has occurred three times with the same side to move
+    Repetition,
+    FiftyMoveRule,
+    /// Neither side has the material to mate
+    InsufficientMaterial,
+}
+
+impl GameResult {
+    pub fn is_draw(&self) -> bool {
+        !matches!(self, GameResult::Ongoing | GameResult::Checkmate { .. })
+    }
+}
+
+/// Keys of the earlier positions that can equal the current one, most recent first. Only
+/// positions since the last capture or pawn move, with the same side to move, can be the same.
+pub(crate) fn repetition_candidates(
+    keys: &[u64],
+    halfmove_clock: u16,
+) -> impl Iterator<Item = &u64> {
+    keys.iter()
+        .rev()
+        .take(halfmove_clock as usize)
+        .skip(1)
+        .step_by(2)
+}
+
+/// A position with the moves that led to it, needed for the draw rules a single Board can't see
+#[derive(Clone, Debug)]
+pub struct Game {
+    board: Board,
+    /// Zobrist keys of the earlier positions, oldest first
+    keys: Vec<u64>,
+    moves: Vec<(Move, Undo)>,
+}
+
+impl Game {
+    pub fn new(board: Board) -> Game {
+        Game {
+            board,
+            keys: Vec::new(),
+            moves: Vec::new(),
+        }
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    /// Zobrist keys of the positions before the current one, oldest first
+    pub fn keys(&self) -> &[u64] {
+        &self.keys
+    }
+
+    pub fn moves(&self) -> impl Iterator<Item = &Move> {
+        self.moves.iter().map(|(m, _)| m)
+    }
+
+    /// Play a legal move
+    pub fn make_move(&mut self, mov: &Move) {
+        self.keys.push(self.board.zobrist());
+        let undo = self.board.make_move_in_place(mov);
+        self.moves.push((*mov, undo));
+    }
+
+    /// Take back the last move, None at the start of the game
+    pub fn unmake_move(&mut self) -> Option<Move> {
+        let (mov, undo) = self.moves.pop()?;
+        self.keys.pop();
+        self.board.unmake_move(&mov, &undo);
+        Some(mov)
+    }
+
+    /// Number of earlier occurrences of the current position
+    pub fn repetitions(&self) -> usize {
+        let key = self.board.zobrist();
+        repetition_candidates(&self.keys, self.board.get_halfmove_clock())
+            .filter(|k| **k == key)
+            .count()
+    }
+
+    /// Threefold repetition
+    pub fn is_repetition(&self) -> bool {
+        self.repetitions() >= 2
+    }
+
+    pub fn is_fifty_move_draw(&self) -> bool {
+        self.board.get_halfmove_clock() >= FIFTY_MOVE_HALFMOVES
+    }
+
+    /// No sequence of legal moves can lead to mate: bare kings, a single minor piece, or only
+    /// bishops that all stand on the same square color
+    pub fn is_insufficient_material(&self) -> bool {
+        let bb = &self.board.bitboard;
+        if bb.white_pawns | bb.black_pawns | bb.white_rooklike | bb.black_rooklike != 0 {
+            return false;
+        }
+
+        let knights = bb.white_knights | bb.black_knights;
+        let bishops = bb.white_bishoplike | bb.black_bishoplike;
+        if (knights | bishops).popcnt() <= 1 {
+            return true;
+        }
+        knights == 0 && (!intersects(bishops, LIGHT_SQUARES) || !intersects(bishops, DARK_SQUARES))
+    }
+
+    pub fn result(&self) -> GameResult {
+        let white = self.board.white_to_move();
+        // Mate takes precedence over the fifty-move rule
+        if self.board.generate_legal_moves().is_empty() {
+            return if self.board.is_in_check(white) {
+                GameResult::Checkmate { white_wins: !white }
+            } else {
+                GameResult::Stalemate
+            };
+        }
+
+        if self.is_insufficient_material() {
+            GameResult::InsufficientMaterial
+        } else if self.is_repetition() {
+            GameResult::Repetition
+        } else if self.is_fifty_move_draw() {
+            GameResult::FiftyMoveRule
+        } else {
+            GameResult::Ongoing
+        }
+    }
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Game::new(Board::default())
+    }
+}
+
+impl From<Board> for Game {
+    fn from(board: Board) -> Self {
+        Game::new(board)
+    }
+}
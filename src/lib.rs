@@ -5,14 +5,24 @@ extern crate lazy_static;
 
 extern crate vampirc_uci;
 
+pub mod attacks;
 pub mod board;
 pub mod constants;
 pub mod engine;
 pub mod eval;
 pub mod fen;
 pub mod flags;
+pub mod game;
 pub mod move_representation;
+pub mod movegen_legal;
 pub mod movegen_movelist;
 pub mod perft;
+pub mod san;
 pub mod search;
+pub mod see;
+pub mod time_management;
+pub mod transposition;
 pub mod uci;
+pub mod uci_options;
+pub mod validation;
+pub mod zobrist;
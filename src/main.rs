@@ -5,11 +5,13 @@ use std::mem::size_of;
 use clap::{App, Arg, SubCommand};
 
 use bench::*;
+use fisk::attacks::SLIDER_ATTACKS;
 use fisk::board::*;
 use fisk::constants::*;
 use fisk::fen::*;
 use fisk::perft::perft_command;
 use fisk::uci::UciState;
+use fisk::zobrist::ZOBRIST;
 
 mod bench;
 
@@ -20,6 +22,8 @@ fn main() {
     lazy_static::initialize(&RANK_ATTACK);
     lazy_static::initialize(&KING_ATTACK);
     lazy_static::initialize(&KING_ATTACK_MASK);
+    lazy_static::initialize(&SLIDER_ATTACKS);
+    lazy_static::initialize(&ZOBRIST);
 
     let opts = App::new("Fisk")
         .version("0.1.0")
@@ -99,7 +103,7 @@ fn main() {
 }
 
 fn generate_and_print(fen_string: String) -> Result<(), ()> {
-    let board = Board::from_fen(&fen_string).ok_or(())?;
+    let board = Board::from_fen(&fen_string).map_err(|_| ())?;
 
     let succ = board.generate_successors();
     for s in &succ {
@@ -173,12 +177,13 @@ fn debug() {
             }
             None => {
                 println!("Finished");
+                // The score is from the side to move, who has been mated unless it is a draw
                 if eval == 0 {
                     println!("Stalemate");
-                } else if eval > 0 {
-                    println!("White won");
-                } else {
+                } else if b.white_to_move() {
                     println!("Black won");
+                } else {
+                    println!("White won");
                 }
                 break;
             }
@@ -1,5 +1,6 @@
 use std::fmt;
 
+use crate::board::Board;
 use crate::constants::SQUARE_NAME;
 
 /// Bit overview:
@@ -10,7 +11,7 @@ use crate::constants::SQUARE_NAME;
 /// 13: special 1
 /// 14: capture
 /// 15: promotion
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub struct Move {
     repr: u16,
 }
@@ -25,6 +26,17 @@ impl Move {
         }
     }
 
+    /// Raw representation, ie for storing in the transposition table
+    #[inline]
+    pub fn to_u16(&self) -> u16 {
+        self.repr
+    }
+
+    #[inline]
+    pub fn from_u16(repr: u16) -> Move {
+        Move { repr }
+    }
+
     pub fn from(&self) -> u8 {
         (self.repr & 0x3F) as u8
     }
@@ -44,6 +56,52 @@ impl Move {
     pub fn is_capture(&self) -> bool {
         (self.repr & (1 << 14)) != 0
     }
+
+    /// Lowercase letter of the piece a promotion promotes to
+    fn promotion_char(&self) -> char {
+        match self.flags_nibble() & 0b11 {
+            0b00 => 'n',
+            0b01 => 'b',
+            0b10 => 'r',
+            0b11 => 'q',
+            _ => unreachable!(),
+        }
+    }
+
+    /// Long algebraic notation as used by UCI, ie e2e4 or e7e8q. Castling is the king move e1g1.
+    pub fn to_uci(&self) -> String {
+        let mut text = format!(
+            "{}{}",
+            SQUARE_NAME[self.from() as usize],
+            SQUARE_NAME[self.to() as usize]
+        );
+        if self.is_promotion() {
+            text.push(self.promotion_char());
+        }
+        text
+    }
+}
+
+/// No legal move in the position is written as the given UCI text
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IllegalMoveError(pub String);
+
+impl fmt::Display for IllegalMoveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Illegal move {}", self.0)
+    }
+}
+
+impl std::error::Error for IllegalMoveError {}
+
+impl Board {
+    /// Find the legal move written in UCI long algebraic notation, see Move::to_uci
+    pub fn parse_uci_move(&self, text: &str) -> Result<Move, IllegalMoveError> {
+        self.generate_legal_moves()
+            .into_iter()
+            .find(|m| m.to_uci() == text)
+            .ok_or_else(|| IllegalMoveError(text.to_string()))
+    }
 }
 
 impl fmt::Display for Move {
@@ -51,13 +109,7 @@ impl fmt::Display for Move {
         let capture = self.is_capture();
         let promotion = self.is_promotion();
         if promotion {
-            let promo = match self.flags_nibble() & 0b11 {
-                0 => 'K',
-                1 => 'B',
-                2 => 'R',
-                3 => 'Q',
-                _ => unreachable!(),
-            };
+            let promo = self.promotion_char().to_ascii_uppercase();
             if capture {
                 write!(
                     f,
@@ -0,0 +1,297 @@
+use bitintr::*;
+
+use crate::attacks::{bishop_attacks, rook_attacks};
+use crate::board::Board;
+use crate::board::PieceKind::*;
+use crate::constants::*;
+use crate::move_representation::Move;
+use crate::movegen_movelist::{black_pawn_moves, white_pawn_moves};
+
+/// Special bits of an en passant capture
+const EN_PASSANT_BITS: u8 = 0b0101;
+
+impl Board {
+    /// Legal moves only. Checkers and pinned pieces are found up front, so that no move has to
+    /// be made to test if it leaves the king in check.
+    pub fn generate_legal_moves(&self) -> Vec<Move> {
+        let white = self.white_to_move();
+        let (our_occupancy, enemy_occupancy) = self.split_occupancy();
+        let total_occupancy = our_occupancy | enemy_occupancy;
+        let king_pos = if white {
+            self.bitboard.white_king
+        } else {
+            self.bitboard.black_king
+        };
+        let king_pos_tzcnt = king_pos.tzcnt() as u8;
+
+        let mut moves = Vec::with_capacity(64);
+        let checkers = self.attackers_to(king_pos_tzcnt, total_occupancy, !white);
+        self.legal_king_moves(
+            king_pos_tzcnt,
+            our_occupancy,
+            enemy_occupancy,
+            checkers,
+            &mut moves,
+        );
+        if checkers.count_ones() > 1 {
+            // Double check, only the king can move
+            return moves;
+        }
+
+        // In check, the checker has to be captured or the check blocked
+        let evasion_mask = if checkers == 0 {
+            !0
+        } else {
+            checkers | between(king_pos_tzcnt, checkers.tzcnt() as u8)
+        };
+        let pinned = self.pinned_pieces(king_pos_tzcnt, our_occupancy, enemy_occupancy, white);
+
+        for i in 0..self.piece_list_len() {
+            let position_tzcnt = self.piece_positions_tzcnt[i];
+            if position_tzcnt == TZCNT_U64_ZEROS {
+                continue;
+            }
+            let kind = self.piece_kinds[i];
+            if white ^ kind.is_white() {
+                continue;
+            }
+
+            let mut allowed = evasion_mask & !our_occupancy;
+            if intersects(1u64 << position_tzcnt, pinned) {
+                // A pinned piece can only move along the pin
+                allowed &= line(king_pos_tzcnt, position_tzcnt);
+            }
+
+            let targets = match kind {
+                WhiteKnight | BlackKnight => KNIGHT_ATTACK_MASKS[position_tzcnt as usize],
+                WhiteBishop | BlackBishop => bishop_attacks(position_tzcnt, total_occupancy),
+                WhiteRook | BlackRook => rook_attacks(position_tzcnt, total_occupancy),
+                WhiteQueen | BlackQueen => {
+                    rook_attacks(position_tzcnt, total_occupancy)
+                        | bishop_attacks(position_tzcnt, total_occupancy)
+                }
+                WhitePawn | BlackPawn => {
+                    self.legal_pawn_moves(
+                        position_tzcnt,
+                        our_occupancy,
+                        enemy_occupancy,
+                        allowed,
+                        &mut moves,
+                    );
+                    continue;
+                }
+                WhiteKing | BlackKing => continue,
+                EmptySquare => unreachable!(),
+            };
+            push_moves(
+                position_tzcnt,
+                targets & allowed,
+                enemy_occupancy,
+                &mut moves,
+            );
+        }
+
+        moves
+    }
+
+    /// Pieces of the given color attacking the square, with sliders blocked by occupancy
+    pub fn attackers_to(&self, square_tzcnt: u8, occupancy: u64, white_attackers: bool) -> u64 {
+        let position = 1u64 << square_tzcnt;
+        let b = &self.bitboard;
+        let (pawns, knights, bishoplike, rooklike, king, pawn_attack_squares) = if white_attackers {
+            (
+                b.white_pawns,
+                b.white_knights,
+                b.white_bishoplike,
+                b.white_rooklike,
+                b.white_king,
+                ((position >> 9) & !FILE_H) | ((position >> 7) & !FILE_A),
+            )
+        } else {
+            (
+                b.black_pawns,
+                b.black_knights,
+                b.black_bishoplike,
+                b.black_rooklike,
+                b.black_king,
+                ((position << 9) & !FILE_A) | ((position << 7) & !FILE_H),
+            )
+        };
+
+        (pawn_attack_squares & pawns)
+            | (KNIGHT_ATTACK_MASKS[square_tzcnt as usize] & knights)
+            | (KING_ATTACK_MASK[square_tzcnt as usize] & king)
+            | (rook_attacks(square_tzcnt, occupancy) & rooklike)
+            | (bishop_attacks(square_tzcnt, occupancy) & bishoplike)
+    }
+
+    /// Our pieces that are the only piece between our king and an enemy slider
+    fn pinned_pieces(
+        &self,
+        king_pos_tzcnt: u8,
+        our_occupancy: u64,
+        enemy_occupancy: u64,
+        white: bool,
+    ) -> u64 {
+        let (enemy_rooklike, enemy_bishoplike) = if white {
+            (self.bitboard.black_rooklike, self.bitboard.black_bishoplike)
+        } else {
+            (self.bitboard.white_rooklike, self.bitboard.white_bishoplike)
+        };
+
+        // Sliders that would attack the king if our pieces were removed
+        let mut snipers = (rook_attacks(king_pos_tzcnt, enemy_occupancy) & enemy_rooklike)
+            | (bishop_attacks(king_pos_tzcnt, enemy_occupancy) & enemy_bishoplike);
+
+        let mut pinned = 0;
+        while snipers != 0 {
+            let sniper_tzcnt = snipers.tzcnt() as u8;
+            let blockers =
+                between(king_pos_tzcnt, sniper_tzcnt) & (our_occupancy | enemy_occupancy);
+            if blockers.count_ones() == 1 {
+                pinned |= blockers & our_occupancy;
+            }
+            snipers &= snipers - 1;
+        }
+
+        pinned
+    }
+
+    fn legal_king_moves(
+        &self,
+        king_pos_tzcnt: u8,
+        our_occupancy: u64,
+        enemy_occupancy: u64,
+        checkers: u64,
+        outvec: &mut Vec<Move>,
+    ) {
+        let white = self.white_to_move();
+        // Without the king, so that squares behind it on a checking slider's ray are attacked
+        let occupancy = (our_occupancy | enemy_occupancy) & !(1u64 << king_pos_tzcnt);
+        let is_safe = |square_tzcnt: u8| self.attackers_to(square_tzcnt, occupancy, !white) == 0;
+
+        let mut targets = KING_ATTACK_MASK[king_pos_tzcnt as usize] & !our_occupancy;
+        while targets != 0 {
+            let target_tzcnt = targets.tzcnt() as u8;
+            if is_safe(target_tzcnt) {
+                let capture = intersects(1u64 << target_tzcnt, enemy_occupancy);
+                outvec.push(Move::new(king_pos_tzcnt, target_tzcnt, capture, 0));
+            }
+            targets &= targets - 1;
+        }
+
+        // Can't castle out of check
+        if checkers != 0 {
+            return;
+        }
+
+        let total_occupancy = our_occupancy | enemy_occupancy;
+        let (kingside, queenside) = if white {
+            (
+                self.can_white_castle_kingside(),
+                self.can_white_castle_queenside(),
+            )
+        } else {
+            (
+                self.can_black_castle_kingside(),
+                self.can_black_castle_queenside(),
+            )
+        };
+        let back_row = if white { ROW_1 } else { ROW_8 };
+
+        if kingside
+            && !intersects(total_occupancy, back_row & (FILE_F | FILE_G))
+            && is_safe(king_pos_tzcnt + 1)
+            && is_safe(king_pos_tzcnt + 2)
+        {
+            outvec.push(Move::new(king_pos_tzcnt, king_pos_tzcnt + 2, false, 0b10));
+        }
+        if queenside
+            && !intersects(total_occupancy, back_row & (FILE_B | FILE_C | FILE_D))
+            && is_safe(king_pos_tzcnt - 1)
+            && is_safe(king_pos_tzcnt - 2)
+        {
+            outvec.push(Move::new(king_pos_tzcnt, king_pos_tzcnt - 2, false, 0b11));
+        }
+    }
+
+    fn legal_pawn_moves(
+        &self,
+        pawn_pos_tzcnt: u8,
+        our_occupancy: u64,
+        enemy_occupancy: u64,
+        allowed: u64,
+        outvec: &mut Vec<Move>,
+    ) {
+        let start = outvec.len();
+        if self.white_to_move() {
+            white_pawn_moves(self, pawn_pos_tzcnt, our_occupancy, enemy_occupancy, outvec);
+        } else {
+            black_pawn_moves(self, pawn_pos_tzcnt, our_occupancy, enemy_occupancy, outvec);
+        }
+
+        let mut kept = start;
+        for i in start..outvec.len() {
+            let mov = outvec[i];
+            let legal = if mov.flags_nibble() == EN_PASSANT_BITS {
+                // Removes two pieces from a row, which may uncover a check along it
+                !self.make_move(&mov).is_in_check(self.white_to_move())
+            } else {
+                intersects(1u64 << mov.to(), allowed)
+            };
+            if legal {
+                outvec[kept] = mov;
+                kept += 1;
+            }
+        }
+        outvec.truncate(kept);
+    }
+}
+
+#[inline]
+fn push_moves(position_tzcnt: u8, mut targets: u64, enemy_occupancy: u64, outvec: &mut Vec<Move>) {
+    while targets != 0 {
+        let target_pos_tzcnt = targets.tzcnt() as u8;
+        let capture = intersects(1u64 << target_pos_tzcnt, enemy_occupancy);
+        outvec.push(Move::new(position_tzcnt, target_pos_tzcnt, capture, 0));
+        targets &= targets - 1;
+    }
+}
+
+fn aligned_like_rook(a_tzcnt: u8, b_tzcnt: u8) -> bool {
+    a_tzcnt / 8 == b_tzcnt / 8 || a_tzcnt % 8 == b_tzcnt % 8
+}
+
+fn aligned_like_bishop(a_tzcnt: u8, b_tzcnt: u8) -> bool {
+    let rank_diff = (a_tzcnt / 8) as i8 - (b_tzcnt / 8) as i8;
+    let file_diff = (a_tzcnt % 8) as i8 - (b_tzcnt % 8) as i8;
+    a_tzcnt != b_tzcnt && rank_diff.abs() == file_diff.abs()
+}
+
+/// Squares strictly between two squares on a common row, file or diagonal, otherwise empty
+pub fn between(a_tzcnt: u8, b_tzcnt: u8) -> u64 {
+    let (a, b) = (1u64 << a_tzcnt, 1u64 << b_tzcnt);
+    if a_tzcnt == b_tzcnt {
+        0
+    } else if aligned_like_rook(a_tzcnt, b_tzcnt) {
+        rook_attacks(a_tzcnt, b) & rook_attacks(b_tzcnt, a)
+    } else if aligned_like_bishop(a_tzcnt, b_tzcnt) {
+        bishop_attacks(a_tzcnt, b) & bishop_attacks(b_tzcnt, a)
+    } else {
+        0
+    }
+}
+
+/// The whole row, file or diagonal through two squares, otherwise empty
+pub fn line(a_tzcnt: u8, b_tzcnt: u8) -> u64 {
+    let ends = (1u64 << a_tzcnt) | (1u64 << b_tzcnt);
+    if a_tzcnt == b_tzcnt {
+        0
+    } else if aligned_like_rook(a_tzcnt, b_tzcnt) {
+        (rook_attacks(a_tzcnt, 0) & rook_attacks(b_tzcnt, 0)) | ends
+    } else if aligned_like_bishop(a_tzcnt, b_tzcnt) {
+        (bishop_attacks(a_tzcnt, 0) & bishop_attacks(b_tzcnt, 0)) | ends
+    } else {
+        0
+    }
+}
@@ -1,5 +1,6 @@
 use bitintr::*;
 
+use crate::attacks::{bishop_attacks, rook_attacks};
 use crate::board::Board;
 use crate::constants::*;
 use crate::move_representation::Move;
@@ -29,22 +30,70 @@ pub fn knight_moves(
     }
 }
 
+/// Captures of the pieces in targets, ie from an attack mask
+#[inline]
+fn push_captures(position_tzcnt: u8, mut targets: u64, outvec: &mut Vec<Move>) {
+    while targets != 0 {
+        let target_pos_tzcnt = targets.tzcnt() as u8;
+        outvec.push(Move::new(position_tzcnt, target_pos_tzcnt, true, 0));
+        targets &= targets - 1;
+    }
+}
+
+pub fn knight_captures(position_tzcnt: u8, enemy_occupancy: u64, outvec: &mut Vec<Move>) {
+    let targets = KNIGHT_ATTACK_MASKS[position_tzcnt as usize] & enemy_occupancy;
+    push_captures(position_tzcnt, targets, outvec);
+}
+
+pub fn bishoplike_captures(
+    position_tzcnt: u8,
+    total_occupancy: u64,
+    enemy_occupancy: u64,
+    outvec: &mut Vec<Move>,
+) {
+    let targets = bishop_attacks(position_tzcnt, total_occupancy) & enemy_occupancy;
+    push_captures(position_tzcnt, targets, outvec);
+}
+
+pub fn rooklike_captures(
+    position_tzcnt: u8,
+    total_occupancy: u64,
+    enemy_occupancy: u64,
+    outvec: &mut Vec<Move>,
+) {
+    let targets = rook_attacks(position_tzcnt, total_occupancy) & enemy_occupancy;
+    push_captures(position_tzcnt, targets, outvec);
+}
+
+pub fn king_captures(position_tzcnt: u8, enemy_occupancy: u64, outvec: &mut Vec<Move>) {
+    let targets = KING_ATTACK_MASK[position_tzcnt as usize] & enemy_occupancy;
+    push_captures(position_tzcnt, targets, outvec);
+}
+
+fn push_promotions(
+    pawn_pos_tzcnt: u8,
+    target_pos_tzcnt: u8,
+    capture: bool,
+    outvec: &mut Vec<Move>,
+) {
+    let mov1 = Move::new(pawn_pos_tzcnt, target_pos_tzcnt, capture, 0b1011);
+    let mov2 = Move::new(pawn_pos_tzcnt, target_pos_tzcnt, capture, 0b1001);
+    let mov3 = Move::new(pawn_pos_tzcnt, target_pos_tzcnt, capture, 0b1010);
+    let mov4 = Move::new(pawn_pos_tzcnt, target_pos_tzcnt, capture, 0b1000);
+
+    outvec.reserve(4);
+    outvec.push(mov1);
+    outvec.push(mov2);
+    outvec.push(mov3);
+    outvec.push(mov4);
+}
+
 fn pawn_capture_pos(pawn_pos_tzcnt: u8, capture_pos: u64, outvec: &mut Vec<Move>) {
     let capture_pos_tzcnt = capture_pos.tzcnt() as u8;
 
     // Capture and promote
     if intersects(capture_pos, ROW_1 | ROW_8) {
-        let mov1 = Move::new(pawn_pos_tzcnt, capture_pos_tzcnt, true, 0b1011);
-        let mov2 = Move::new(pawn_pos_tzcnt, capture_pos_tzcnt, true, 0b1001);
-        let mov3 = Move::new(pawn_pos_tzcnt, capture_pos_tzcnt, true, 0b1010);
-        let mov4 = Move::new(pawn_pos_tzcnt, capture_pos_tzcnt, true, 0b1000);
-
-        outvec.reserve(4);
-        outvec.push(mov1);
-        outvec.push(mov2);
-        outvec.push(mov3);
-        outvec.push(mov4);
-
+        push_promotions(pawn_pos_tzcnt, capture_pos_tzcnt, true, outvec);
         return;
     }
 
@@ -68,16 +117,7 @@ pub fn white_pawn_moves(
     let free_square_in_front = !intersects(pos_front, total_occupancy);
     if free_square_in_front && intersects(pos_front, ROW_8) {
         // Promote
-        let mov1 = Move::new(pawn_pos_tzcnt, pos_front_tzcnt, false, 0b1011);
-        let mov2 = Move::new(pawn_pos_tzcnt, pos_front_tzcnt, false, 0b1001);
-        let mov3 = Move::new(pawn_pos_tzcnt, pos_front_tzcnt, false, 0b1010);
-        let mov4 = Move::new(pawn_pos_tzcnt, pos_front_tzcnt, false, 0b1000);
-
-        outvec.reserve(4);
-        outvec.push(mov1);
-        outvec.push(mov2);
-        outvec.push(mov3);
-        outvec.push(mov4);
+        push_promotions(pawn_pos_tzcnt, pos_front_tzcnt, false, outvec);
     } else if free_square_in_front {
         // pawn short forward move
         let mov = Move::new(pawn_pos_tzcnt, pos_front_tzcnt, false, 0);
@@ -94,6 +134,34 @@ pub fn white_pawn_moves(
         }
     }
 
+    white_pawn_attack_moves(board, pawn_pos_tzcnt, enemy_occupancy, outvec);
+}
+
+/// Captures (including en passant) and promotions of a white pawn
+pub fn white_pawn_captures(
+    board: &Board,
+    pawn_pos_tzcnt: u8,
+    our_occupancy: u64,
+    enemy_occupancy: u64,
+    outvec: &mut Vec<Move>,
+) {
+    let pos_front_tzcnt = pawn_pos_tzcnt + 8;
+    let pos_front = 1 << pos_front_tzcnt;
+    if intersects(pos_front, ROW_8) && !intersects(pos_front, our_occupancy | enemy_occupancy) {
+        push_promotions(pawn_pos_tzcnt, pos_front_tzcnt, false, outvec);
+    }
+
+    white_pawn_attack_moves(board, pawn_pos_tzcnt, enemy_occupancy, outvec);
+}
+
+fn white_pawn_attack_moves(
+    board: &Board,
+    pawn_pos_tzcnt: u8,
+    enemy_occupancy: u64,
+    outvec: &mut Vec<Move>,
+) {
+    let position = 1u64 << pawn_pos_tzcnt;
+
     if !intersects(position, FILE_A) {
         let front_left_pos = position << 7;
         if intersects(front_left_pos, enemy_occupancy) {
@@ -136,16 +204,7 @@ pub fn black_pawn_moves(
     let free_square_in_front = !intersects(pos_front, total_occupancy);
     if free_square_in_front && intersects(pos_front, ROW_1) {
         // Promote
-        let mov1 = Move::new(pawn_pos_tzcnt, pos_front_tzcnt, false, 0b1011);
-        let mov2 = Move::new(pawn_pos_tzcnt, pos_front_tzcnt, false, 0b1001);
-        let mov3 = Move::new(pawn_pos_tzcnt, pos_front_tzcnt, false, 0b1010);
-        let mov4 = Move::new(pawn_pos_tzcnt, pos_front_tzcnt, false, 0b1000);
-
-        outvec.reserve(4);
-        outvec.push(mov1);
-        outvec.push(mov2);
-        outvec.push(mov3);
-        outvec.push(mov4);
+        push_promotions(pawn_pos_tzcnt, pos_front_tzcnt, false, outvec);
     } else if free_square_in_front {
         // pawn short forward move
         let mov = Move::new(pawn_pos_tzcnt, pos_front_tzcnt, false, 0);
@@ -162,6 +221,34 @@ pub fn black_pawn_moves(
         }
     }
 
+    black_pawn_attack_moves(board, pawn_pos_tzcnt, enemy_occupancy, outvec);
+}
+
+/// Captures (including en passant) and promotions of a black pawn
+pub fn black_pawn_captures(
+    board: &Board,
+    pawn_pos_tzcnt: u8,
+    our_occupancy: u64,
+    enemy_occupancy: u64,
+    outvec: &mut Vec<Move>,
+) {
+    let pos_front_tzcnt = pawn_pos_tzcnt - 8;
+    let pos_front = 1 << pos_front_tzcnt;
+    if intersects(pos_front, ROW_1) && !intersects(pos_front, our_occupancy | enemy_occupancy) {
+        push_promotions(pawn_pos_tzcnt, pos_front_tzcnt, false, outvec);
+    }
+
+    black_pawn_attack_moves(board, pawn_pos_tzcnt, enemy_occupancy, outvec);
+}
+
+fn black_pawn_attack_moves(
+    board: &Board,
+    pawn_pos_tzcnt: u8,
+    enemy_occupancy: u64,
+    outvec: &mut Vec<Move>,
+) {
+    let position = 1u64 << pawn_pos_tzcnt;
+
     if position & FILE_A == 0 {
         let pos_file_lower = position >> 9;
         if intersects(pos_file_lower, enemy_occupancy) {
@@ -199,118 +286,20 @@ fn ep_eligible_positions_mask(twofront_square: u64) -> u64 {
     }
 }
 
-fn bishoplike_target_square(
-    position_tzcnt: u8,
-    target_pos: u64,
-    our_occupancy: u64,
-    enemy_occupancy: u64,
-    outvec: &mut Vec<Move>,
-) -> bool {
-    if (target_pos & our_occupancy) != 0 {
-        // Abort
-        return false;
-    }
-    let target_pos_tzcnt = target_pos.tzcnt() as u8;
-    let capture = (target_pos & enemy_occupancy) != 0;
-    let mov = Move::new(position_tzcnt, target_pos_tzcnt, capture, 0);
-    outvec.push(mov);
-
-    !capture
-}
-
 pub fn bishoplike_moves(
     position: u64,
     our_occupancy: u64,
     enemy_occupancy: u64,
     outvec: &mut Vec<Move>,
 ) {
-    let pos_tzcnt = position.tzcnt() as u8;
-
-    fn is_at_top(position: u64) -> bool {
-        intersects(position, ROW_8)
-    }
-    fn is_at_bottom(position: u64) -> bool {
-        intersects(position, ROW_1)
-    }
-    fn is_at_left(position: u64) -> bool {
-        intersects(position, FILE_A)
-    }
-    fn is_at_right(position: u64) -> bool {
-        intersects(position, FILE_H)
-    }
-
-    let top = is_at_top(position);
-    let bottom = is_at_bottom(position);
-    let right = is_at_right(position);
-    let left = is_at_left(position);
-
-    if !top && !right {
-        let mut target_pos = position << 9;
-        loop {
-            let should_continue = bishoplike_target_square(
-                pos_tzcnt,
-                target_pos,
-                our_occupancy,
-                enemy_occupancy,
-                outvec,
-            );
-            if !should_continue || is_at_top(target_pos) || is_at_right(target_pos) {
-                break;
-            }
-            target_pos <<= 9;
-        }
-    }
-
-    if !top && !left {
-        let mut target_pos = position << 7;
-        loop {
-            let should_continue = bishoplike_target_square(
-                pos_tzcnt,
-                target_pos,
-                our_occupancy,
-                enemy_occupancy,
-                outvec,
-            );
-            if !should_continue || is_at_top(target_pos) || is_at_left(target_pos) {
-                break;
-            }
-            target_pos <<= 7;
-        }
-    }
-
-    if !bottom && !right {
-        let mut target_pos = position >> 7;
-        loop {
-            let should_continue = bishoplike_target_square(
-                pos_tzcnt,
-                target_pos,
-                our_occupancy,
-                enemy_occupancy,
-                outvec,
-            );
-            if !should_continue || is_at_bottom(target_pos) || is_at_right(target_pos) {
-                break;
-            }
-            target_pos >>= 7;
-        }
-    }
-
-    if !bottom && !left {
-        let mut target_pos = position >> 9;
-        loop {
-            let should_continue = bishoplike_target_square(
-                pos_tzcnt,
-                target_pos,
-                our_occupancy,
-                enemy_occupancy,
-                outvec,
-            );
-            if !should_continue || is_at_bottom(target_pos) || is_at_left(target_pos) {
-                break;
-            }
-            target_pos >>= 9;
-        }
-    }
+    let position_tzcnt = position.tzcnt() as u8;
+    let targets = bishop_attacks(position_tzcnt, our_occupancy | enemy_occupancy);
+    slider_moves(
+        position_tzcnt,
+        targets & !our_occupancy,
+        enemy_occupancy,
+        outvec,
+    );
 }
 
 pub fn rooklike_moves(
@@ -318,116 +307,29 @@ pub fn rooklike_moves(
     our_occupancy: u64,
     enemy_occupancy: u64,
     outvec: &mut Vec<Move>,
-) {
-    file_slide_moves(position, our_occupancy, enemy_occupancy, outvec);
-    row_slide_moves(position, our_occupancy, enemy_occupancy, outvec);
-}
-
-fn rooklike_target_square(
-    position_tzcnt: u8,
-    target_pos: u64,
-    our_occupancy: u64,
-    enemy_occupancy: u64,
-    outvec: &mut Vec<Move>,
-) -> bool {
-    if (target_pos & our_occupancy) != 0 {
-        // Abort
-        return false;
-    }
-
-    let capture = (target_pos & enemy_occupancy) != 0;
-    let target_pos_tzcnt = target_pos.tzcnt() as u8;
-    let mov = Move::new(position_tzcnt, target_pos_tzcnt, capture, 0);
-    outvec.push(mov);
-
-    !capture
-}
-
-fn file_slide_moves(
-    position: u64,
-    our_occupancy: u64,
-    enemy_occupancy: u64,
-    outvec: &mut Vec<Move>,
 ) {
     let position_tzcnt = position.tzcnt() as u8;
-    if position & ROW_8 == 0 {
-        // Not in row 8, ie can move upwards
-        let mut target_pos = position << 8;
-        loop {
-            let should_continue = rooklike_target_square(
-                position_tzcnt,
-                target_pos,
-                our_occupancy,
-                enemy_occupancy,
-                outvec,
-            );
-            if !should_continue || (target_pos & ROW_8) != 0 {
-                break;
-            }
-            target_pos <<= 8;
-        }
-    }
-
-    if position & ROW_1 == 0 {
-        // Not in row 1, ie can move downwards
-        let mut target_pos = position >> 8;
-        loop {
-            let should_continue = rooklike_target_square(
-                position_tzcnt,
-                target_pos,
-                our_occupancy,
-                enemy_occupancy,
-                outvec,
-            );
-            if !should_continue || (target_pos & ROW_1) != 0 {
-                break;
-            }
-            target_pos >>= 8;
-        }
-    }
+    let targets = rook_attacks(position_tzcnt, our_occupancy | enemy_occupancy);
+    slider_moves(
+        position_tzcnt,
+        targets & !our_occupancy,
+        enemy_occupancy,
+        outvec,
+    );
 }
 
-fn row_slide_moves(
-    position: u64,
-    our_occupancy: u64,
+#[inline]
+fn slider_moves(
+    position_tzcnt: u8,
+    mut targets: u64,
     enemy_occupancy: u64,
     outvec: &mut Vec<Move>,
 ) {
-    let position_tzcnt = position.tzcnt() as u8;
-    if !intersects(position, FILE_H) {
-        // Not in file H, ie can move in positive file direction
-        let mut target_pos = position << 1;
-        loop {
-            let should_continue = rooklike_target_square(
-                position_tzcnt,
-                target_pos,
-                our_occupancy,
-                enemy_occupancy,
-                outvec,
-            );
-            if !should_continue || intersects(target_pos, FILE_H) {
-                break;
-            }
-            target_pos <<= 1;
-        }
-    }
-
-    if !intersects(position, FILE_A) {
-        // Not in file A, ie can move in negative file direction
-        let mut target_pos = position >> 1;
-        loop {
-            let should_continue = rooklike_target_square(
-                position_tzcnt,
-                target_pos,
-                our_occupancy,
-                enemy_occupancy,
-                outvec,
-            );
-            if !should_continue || intersects(target_pos, FILE_A) {
-                break;
-            }
-            target_pos >>= 1;
-        }
+    while targets != 0 {
+        let target_pos_tzcnt = targets.tzcnt() as u8;
+        let capture = intersects(1u64 << target_pos_tzcnt, enemy_occupancy);
+        outvec.push(Move::new(position_tzcnt, target_pos_tzcnt, capture, 0));
+        targets &= targets - 1;
     }
 }
 
@@ -149,6 +149,14 @@ fn init_perft_configs() -> HashMap<&'static str, PerftConfig> {
     map
 }
 
+/// Positions of the perft configs, for tests that want a varied set of positions
+pub fn perft_fens() -> Vec<&'static str> {
+    let mut fens: Vec<&'static str> = PERFT_CONFIGS.values().map(|c| c.fen).collect();
+    fens.sort_unstable();
+    fens.dedup();
+    fens
+}
+
 fn print_perft_configs() {
     println!("The following perft configs are available:");
     for (key, _) in PERFT_CONFIGS.iter() {
@@ -160,34 +168,57 @@ fn perft_results(init_board: &Board, config: &PerftConfig) -> Vec<usize> {
     let mut results: Vec<usize> = vec![0; config.depth + 1];
 
     results[0] = 1;
-    perft_recurse(init_board, config, 1, &mut results);
+    let mut board = *init_board;
+    perft_recurse(&mut board, config, 1, &mut results);
 
     results
 }
 
-fn perft_recurse(board: &Board, config: &PerftConfig, depth: usize, results: &mut Vec<usize>) {
+fn perft_recurse(board: &mut Board, config: &PerftConfig, depth: usize, results: &mut Vec<usize>) {
     if depth > config.depth {
         return;
     }
 
-    let successors = board.generate_successors();
-    let mut count = 0usize;
-    for s in successors {
-        if s.is_in_check(!s.white_to_move()) {
-            // illegal successor
-            continue;
-        }
-        perft_recurse(&s, config, depth + 1, results);
-
-        count += 1;
+    let moves = board.generate_legal_moves();
+    for m in &moves {
+        let undo = board.make_move_in_place(m);
+        perft_recurse(board, config, depth + 1, results);
+        board.unmake_move(m, &undo);
     }
+    let count = moves.len();
     results[depth as usize] += count;
 }
 
+/// Number of leaf nodes of the legal move tree of the given depth
+pub fn perft(board: &Board, depth: usize) -> usize {
+    let mut board = *board;
+    perft_in_place(&mut board, depth)
+}
+
+/// Like perft, making and unmaking moves on a single board
+fn perft_in_place(board: &mut Board, depth: usize) -> usize {
+    if depth == 0 {
+        return 1;
+    }
+
+    let moves = board.generate_legal_moves();
+    if depth == 1 {
+        return moves.len();
+    }
+
+    let mut nodes = 0;
+    for m in &moves {
+        let undo = board.make_move_in_place(m);
+        nodes += perft_in_place(board, depth - 1);
+        board.unmake_move(m, &undo);
+    }
+    nodes
+}
+
 fn run_config(name: &str, config: &PerftConfig) -> Result<(), PerftError> {
     println!("Running perft config {}", name);
     let init_board = Board::from_fen(config.fen)
-        .unwrap_or_else(|| panic!("Could not parse FEN string: {}", config.fen));
+        .unwrap_or_else(|e| panic!("Could not parse FEN string {}: {}", config.fen, e));
 
     let results = perft_results(&init_board, config);
 
@@ -246,13 +277,9 @@ fn test_single(name: &str, config: &PerftConfig) {
 }
 
 fn perft_debug(board: &Board, search_depth: usize) {
-    let successors = board.generate_successors();
     let mut sum = 0;
-    for s in successors {
-        if s.is_in_check(!s.white_to_move()) {
-            // illegal successor
-            continue;
-        }
+    for m in board.generate_legal_moves() {
+        let s = board.make_move(&m);
         // Hack
         let config = PerftConfig {
             fen: "",
@@ -262,7 +289,7 @@ fn perft_debug(board: &Board, search_depth: usize) {
 
         let results = perft_results(&s, &config);
         let count = results.last().unwrap();
-        println!("{}", count);
+        println!("{}: {}", m, count);
         sum += count;
     }
     println!("Sum {}", sum);
@@ -316,13 +343,13 @@ pub fn perft_command(args: &ArgMatches) {
             Some(c) => c.fen,
         };
 
-        let board = Board::from_fen(fen);
-        if board.is_none() {
-            println!("Bad FEN string: {}", which);
-            return;
-        }
-
-        let board = board.unwrap();
+        let board = match Board::from_fen(fen) {
+            Ok(board) => board,
+            Err(e) => {
+                println!("Bad FEN string {}: {}", which, e);
+                return;
+            }
+        };
         perft_debug(&board, 2); // TODO
         return;
     }
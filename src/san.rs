@@ -0,0 +1,213 @@
+use std::fmt;
+
+use crate::board::Board;
+use crate::board::PieceKind::{self, *};
+use crate::constants::SQUARE_NAME;
+use crate::move_representation::Move;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SanError {
+    /// Not Standard Algebraic Notation
+    Malformed(String),
+    /// No legal move in the position matches
+    NoMatchingMove(String),
+    /// More than one legal move matches, the move needs disambiguation
+    Ambiguous(String),
+}
+
+impl fmt::Display for SanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SanError::Malformed(san) => write!(f, "Malformed move '{}'", san),
+            SanError::NoMatchingMove(san) => write!(f, "No legal move matches '{}'", san),
+            SanError::Ambiguous(san) => write!(f, "Ambiguous move '{}'", san),
+        }
+    }
+}
+
+impl std::error::Error for SanError {}
+
+/// Piece letter regardless of color, None for pawns
+fn piece_letter(kind: PieceKind) -> Option<char> {
+    match kind {
+        WhiteKnight | BlackKnight => Some('N'),
+        WhiteBishop | BlackBishop => Some('B'),
+        WhiteRook | BlackRook => Some('R'),
+        WhiteQueen | BlackQueen => Some('Q'),
+        WhiteKing | BlackKing => Some('K'),
+        WhitePawn | BlackPawn | EmptySquare => None,
+    }
+}
+
+/// Letter of the piece a promotion promotes to
+fn promotion_letter(mov: &Move) -> char {
+    match mov.flags_nibble() & 0b11 {
+        0b00 => 'N',
+        0b01 => 'B',
+        0b10 => 'R',
+        0b11 => 'Q',
+        _ => unreachable!(),
+    }
+}
+
+fn file_char(square: u8) -> char {
+    (b'a' + square % 8) as char
+}
+
+fn rank_char(square: u8) -> char {
+    (b'1' + square / 8) as char
+}
+
+impl Board {
+    /// Standard Algebraic Notation of a legal move https://en.wikipedia.org/wiki/Algebraic_notation_(chess)
+    pub fn move_to_san(&self, mov: &Move) -> String {
+        let mut san = match mov.flags_nibble() {
+            0b0010 => "O-O".to_string(),
+            0b0011 => "O-O-O".to_string(),
+            _ => self.san_without_suffix(mov),
+        };
+
+        let after = self.make_move(mov);
+        if after.is_in_check(after.white_to_move()) {
+            if after.generate_legal_moves().is_empty() {
+                san.push('#');
+            } else {
+                san.push('+');
+            }
+        }
+        san
+    }
+
+    fn san_without_suffix(&self, mov: &Move) -> String {
+        let from = mov.from();
+        let to = mov.to();
+        let mut san = String::new();
+
+        match piece_letter(self.kind_at(from)) {
+            None => {
+                // Pawns are identified by the file they capture from
+                if mov.is_capture() {
+                    san.push(file_char(from));
+                }
+            }
+            Some(letter) => {
+                san.push(letter);
+
+                // Other pieces of the same kind that can move to the same square
+                let others: Vec<u8> = self
+                    .generate_legal_moves()
+                    .iter()
+                    .filter(|m| {
+                        m.to() == to
+                            && m.from() != from
+                            && self.kind_at(m.from()) == self.kind_at(from)
+                    })
+                    .map(|m| m.from())
+                    .collect();
+                if !others.is_empty() {
+                    let same_file = others.iter().any(|o| o % 8 == from % 8);
+                    let same_rank = others.iter().any(|o| o / 8 == from / 8);
+                    if !same_file {
+                        san.push(file_char(from));
+                    } else if !same_rank {
+                        san.push(rank_char(from));
+                    } else {
+                        san.push(file_char(from));
+                        san.push(rank_char(from));
+                    }
+                }
+            }
+        }
+
+        if mov.is_capture() {
+            san.push('x');
+        }
+        san += SQUARE_NAME[to as usize];
+        if mov.is_promotion() {
+            san.push('=');
+            san.push(promotion_letter(mov));
+        }
+        san
+    }
+
+    /// Find the legal move written in Standard Algebraic Notation. Check, mate and annotation
+    /// suffixes are ignored, as is a missing or superfluous capture marker.
+    pub fn parse_san(&self, san: &str) -> Result<Move, SanError> {
+        let malformed = || SanError::Malformed(san.to_string());
+        let text = san.trim().trim_end_matches(['+', '#', '!', '?']);
+
+        let castling_bits = match text {
+            "O-O" | "0-0" => Some(0b0010),
+            "O-O-O" | "0-0-0" => Some(0b0011),
+            _ => None,
+        };
+        if let Some(bits) = castling_bits {
+            return self
+                .generate_legal_moves()
+                .into_iter()
+                .find(|m| m.flags_nibble() == bits)
+                .ok_or_else(|| SanError::NoMatchingMove(san.to_string()));
+        }
+
+        let mut chars: Vec<char> = text.chars().filter(|c| *c != 'x').collect();
+
+        let piece = match chars.first() {
+            Some(c @ ('N' | 'B' | 'R' | 'Q' | 'K')) => {
+                let piece = *c;
+                chars.remove(0);
+                Some(piece)
+            }
+            _ => None,
+        };
+
+        let promotion = match chars.last() {
+            Some(c @ ('N' | 'B' | 'R' | 'Q')) if piece.is_none() => {
+                let promotion = *c;
+                chars.pop();
+                if chars.last() == Some(&'=') {
+                    chars.pop();
+                }
+                Some(promotion)
+            }
+            _ => None,
+        };
+
+        if chars.len() < 2 || chars.len() > 4 {
+            return Err(malformed());
+        }
+        let destination: String = chars[chars.len() - 2..].iter().collect();
+        let to = SQUARE_NAME
+            .iter()
+            .position(|s| *s == destination)
+            .ok_or_else(malformed)? as u8;
+
+        let mut from_file = None;
+        let mut from_rank = None;
+        for c in &chars[..chars.len() - 2] {
+            match c {
+                'a'..='h' if from_file.is_none() => from_file = Some(*c as u8 - b'a'),
+                '1'..='8' if from_rank.is_none() => from_rank = Some(*c as u8 - b'1'),
+                _ => return Err(malformed()),
+            }
+        }
+
+        let matching: Vec<Move> = self
+            .generate_legal_moves()
+            .into_iter()
+            .filter(|m| {
+                m.to() == to
+                    && piece_letter(self.kind_at(m.from())) == piece
+                    && from_file.is_none_or(|file| m.from() % 8 == file)
+                    && from_rank.is_none_or(|rank| m.from() / 8 == rank)
+                    && m.is_promotion() == promotion.is_some()
+                    && (!m.is_promotion() || Some(promotion_letter(m)) == promotion)
+            })
+            .collect();
+
+        match matching.len() {
+            0 => Err(SanError::NoMatchingMove(san.to_string())),
+            1 => Ok(matching[0]),
+            _ => Err(SanError::Ambiguous(san.to_string())),
+        }
+    }
+}
@@ -1,95 +1,869 @@
-use crate::board::Board;
-use crate::eval::{INF, NEGINF};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use bitintr::Popcnt;
+
+use crate::board::{Board, PieceKind};
+use crate::eval::{is_mate_score, piece_value, INF, MATE, MAX_PLY, NEGINF, PAWN, QUEEN};
+use crate::game::{repetition_candidates, Game, FIFTY_MOVE_HALFMOVES};
 use crate::move_representation::Move;
+use crate::time_management::{TimeLimits, TimeManager};
+use crate::transposition::{score_from_tt, score_to_tt, Bound, TranspositionTable};
+
+/// How many nodes to search between each check of the stop flag, must be a power of two
+const STOP_CHECK_INTERVAL: u64 = 1024;
+
+/// Deepest iteration searched when only time or a stop command limits the search
+pub const MAX_DEPTH: usize = MAX_PLY / 2;
+
+/// Time between progress reports while an iteration is running
+const INFO_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Table size of the one-off searches in Board::best_move and Game::best_move, which are called
+/// once per move and shouldn't allocate a full size table each time
+const ONE_OFF_TT_SIZE_MB: usize = 1;
+
+/// Move ordering buckets, moves within the capture and quiet buckets are ordered by their scores
+const PV_MOVE_SCORE: i32 = 1 << 30;
+const HASH_MOVE_SCORE: i32 = 1 << 29;
+const CAPTURE_SCORE: i32 = 1 << 28;
+const KILLER_SCORE: i32 = 1 << 27;
+/// History scores are halved when one reaches this, so that they stay below the killers
+const MAX_HISTORY_SCORE: i32 = 1 << 20;
+
+/// Shallowest depth where a null move is tried
+const NULL_MOVE_MIN_DEPTH: usize = 3;
+/// Null move searches are reduced by one ply more above this depth
+const NULL_MOVE_DEEP: usize = 6;
+/// Sides with this few pieces besides king and pawns are likely to be in zugzwang
+const ZUGZWANG_PIECES: u32 = 2;
+
+/// Deepest depth where futility pruning and reverse futility pruning are used
+const FUTILITY_DEPTH: usize = 3;
+/// How much a quiet move may gain per ply of depth left, for futility pruning
+const FUTILITY_MARGIN: i32 = 2 * PAWN;
+/// How much the opponent may gain per ply of depth left, for reverse futility pruning
+const REVERSE_FUTILITY_MARGIN: i32 = PAWN;
+
+/// Moves searched at full depth before late move reductions start
+const LMR_FULL_DEPTH_MOVES: usize = 3;
+/// Shallowest depth where late moves are reduced
+const LMR_MIN_DEPTH: usize = 3;
+/// Moves this late at this depth or deeper are reduced by two plies
+const LMR_DEEP: usize = 6;
+
+/// Search heuristics that can be turned off, to measure their effect
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct SearchFeatures {
+    /// Order quiet moves by killer moves and the history heuristic
+    pub quiet_move_ordering: bool,
+    /// Prune when passing the turn still fails high https://www.chessprogramming.org/Null_Move_Pruning
+    pub null_move: bool,
+    /// Confirm null move cutoffs in positions prone to zugzwang with a reduced normal search
+    pub null_move_verification: bool,
+    /// Search quiet moves late in the move ordering at reduced depth
+    /// https://www.chessprogramming.org/Late_Move_Reductions
+    pub late_move_reductions: bool,
+    /// Skip quiet moves near the leaves when the static evaluation is far below alpha
+    /// https://www.chessprogramming.org/Futility_Pruning
+    pub futility_pruning: bool,
+    /// Return near the leaves when the static evaluation is far above beta
+    /// https://www.chessprogramming.org/Reverse_Futility_Pruning
+    pub reverse_futility_pruning: bool,
+    /// Search moves after the first with a null window https://www.chessprogramming.org/Principal_Variation_Search
+    pub pvs: bool,
+}
+
+impl Default for SearchFeatures {
+    fn default() -> Self {
+        SearchFeatures {
+            quiet_move_ordering: true,
+            null_move: true,
+            null_move_verification: true,
+            late_move_reductions: true,
+            futility_pruning: true,
+            reverse_futility_pruning: true,
+            pvs: true,
+        }
+    }
+}
 
 impl Board {
+    /// Score from the side to move's perspective, and the move to play
     pub fn best_move(&self, depth: usize) -> (i32, Option<Move>) {
-        self.minimax(depth, NEGINF, INF)
+        let tt = TranspositionTable::new(ONE_OFF_TT_SIZE_MB);
+        let result = Search::new(&tt).iterative_deepening(self, depth);
+        (result.score, result.best_move())
     }
+}
 
-    fn minimax(&self, depth: usize, mut alpha: i32, mut beta: i32) -> (i32, Option<Move>) {
-        let white = self.white_to_move();
+impl Game {
+    /// Like Board::best_move, but avoiding or aiming for repetitions of earlier positions
+    pub fn best_move(&self, depth: usize) -> (i32, Option<Move>) {
+        let tt = TranspositionTable::new(ONE_OFF_TT_SIZE_MB);
+        let result = Search::new(&tt)
+            .with_history(self.keys())
+            .iterative_deepening(self.board(), depth);
+        (result.score, result.best_move())
+    }
+}
 
-        if depth == 0 {
-            return (self.eval(), None);
+/// Result of the deepest completed iteration
+#[derive(Clone, Debug)]
+pub struct SearchResult {
+    pub depth: usize,
+    /// From the side to move's perspective
+    pub score: i32,
+    /// Principal variation, the expected line of play from the root
+    pub pv: Vec<Move>,
+}
+
+impl SearchResult {
+    pub fn best_move(&self) -> Option<Move> {
+        self.pv.first().copied()
+    }
+}
+
+/// Progress report, given after each completed iteration and periodically during long iterations
+pub struct SearchInfo<'a> {
+    pub depth: usize,
+    /// Deepest ply reached, including quiescence search
+    pub seldepth: usize,
+    /// Which of the best lines this is, starting at 1
+    pub multipv: usize,
+    /// From the side to move's perspective, None while the iteration is running
+    pub score: Option<i32>,
+    pub nodes: u64,
+    pub time: Duration,
+    /// Empty while the iteration is running
+    pub pv: &'a [Move],
+    /// Permille of the transposition table in use
+    pub hashfull: usize,
+}
+
+impl SearchInfo<'_> {
+    pub fn nps(&self) -> u64 {
+        let micros = self.time.as_micros().max(1);
+        (self.nodes as u128 * 1_000_000 / micros) as u64
+    }
+}
+
+type InfoCallback<'a> = Box<dyn FnMut(&SearchInfo) + 'a>;
+
+/// State shared by all nodes of a search
+pub struct Search<'a> {
+    tt: &'a TranspositionTable,
+    stop: Arc<AtomicBool>,
+    stopped: bool,
+    nodes: u64,
+    /// PV of the previous iteration, searched first in the next one
+    prev_pv: Vec<Move>,
+    follow_pv: bool,
+    time_limits: TimeLimits,
+    time: TimeManager,
+    /// Set when the pondered move is played, see with_ponderhit_flag
+    ponderhit: Option<Arc<AtomicBool>>,
+    pondering: bool,
+    /// Depth of the running iteration
+    depth: usize,
+    seldepth: usize,
+    info_callback: Option<InfoCallback<'a>>,
+    last_info: Instant,
+    /// Number of best lines to search and report
+    multi_pv: usize,
+    /// Root moves skipped when searching the next best line
+    excluded_root_moves: Vec<Move>,
+    /// Zobrist keys of the positions before the current node, the game history followed by the
+    /// searched line
+    history: Vec<u64>,
+    features: SearchFeatures,
+    /// Two quiet moves per ply that caused beta cutoffs in sibling nodes
+    /// https://www.chessprogramming.org/Killer_Heuristic
+    killers: [[Option<Move>; 2]; MAX_PLY],
+    /// Butterfly table of quiet move cutoffs indexed by side to move, from and to square
+    /// https://www.chessprogramming.org/History_Heuristic
+    history_scores: Box<[[[i32; 64]; 64]; 2]>,
+    /// No null move in the next node searched, set after a null move and for verification
+    /// searches
+    skip_null_move: bool,
+}
+
+impl<'a> Search<'a> {
+    pub fn new(tt: &'a TranspositionTable) -> Self {
+        Search {
+            tt,
+            stop: Arc::new(AtomicBool::new(false)),
+            stopped: false,
+            nodes: 0,
+            prev_pv: Vec::new(),
+            follow_pv: false,
+            time_limits: TimeLimits::unlimited(),
+            time: TimeManager::start(TimeLimits::unlimited()),
+            ponderhit: None,
+            pondering: false,
+            depth: 0,
+            seldepth: 0,
+            info_callback: None,
+            last_info: Instant::now(),
+            multi_pv: 1,
+            excluded_root_moves: Vec::new(),
+            history: Vec::new(),
+            features: SearchFeatures::default(),
+            killers: [[None; 2]; MAX_PLY],
+            history_scores: Box::new([[[0; 64]; 64]; 2]),
+            skip_null_move: false,
         }
+    }
 
-        let mut moves = self.generate_pseudo_legal_moves();
-        order_moves(&mut moves);
+    /// Use an external stop flag, which when set makes the search return the result of the
+    /// deepest completed iteration. The first iteration is always completed.
+    pub fn with_stop_flag(mut self, stop: Arc<AtomicBool>) -> Self {
+        self.stop = stop;
+        self
+    }
 
-        let max = white;
-        let mut best: Option<(i32, Move)> = None;
+    /// Abort the search at the hard limit, and don't start new iterations after the soft limit.
+    /// The clock starts when iterative_deepening is called.
+    pub fn with_time_limits(mut self, time_limits: TimeLimits) -> Self {
+        self.time_limits = time_limits;
+        self
+    }
+
+    /// Ponder without time limits until the flag is set, then apply the time limits counted from
+    /// that moment
+    pub fn with_ponderhit_flag(mut self, ponderhit: Arc<AtomicBool>) -> Self {
+        self.ponderhit = Some(ponderhit);
+        self
+    }
 
-        if max {
-            for m in moves.iter() {
-                let b = self.make_move(m);
-                if b.is_in_check(white) {
-                    // Illegal, can't move into check
-                    continue;
+    /// Report search progress to the callback
+    pub fn with_info_callback(mut self, callback: impl FnMut(&SearchInfo) + 'a) -> Self {
+        self.info_callback = Some(Box::new(callback));
+        self
+    }
+
+    /// Search and report the multi_pv best lines, the result is the best one
+    pub fn with_multi_pv(mut self, multi_pv: usize) -> Self {
+        self.multi_pv = multi_pv.max(1);
+        self
+    }
+
+    /// Zobrist keys of the positions before the root, oldest first, see Game::keys
+    pub fn with_history(mut self, keys: &[u64]) -> Self {
+        self.history = keys.to_vec();
+        self
+    }
+
+    pub fn with_features(mut self, features: SearchFeatures) -> Self {
+        self.features = features;
+        self
+    }
+
+    pub fn stop_flag(&self) -> Arc<AtomicBool> {
+        self.stop.clone()
+    }
+
+    pub fn nodes(&self) -> u64 {
+        self.nodes
+    }
+
+    /// Search depth 1, 2, 3... up to max_depth, or until stopped. The caller starts a new
+    /// search in a reused table with TranspositionTable::new_search, as the table may be shared
+    /// with other threads searching the same position.
+    pub fn iterative_deepening(&mut self, board: &Board, max_depth: usize) -> SearchResult {
+        self.stopped = false;
+        self.prev_pv.clear();
+        self.pondering = match &self.ponderhit {
+            Some(ponderhit) => !ponderhit.load(Ordering::Relaxed),
+            None => false,
+        };
+        self.time = if self.pondering {
+            TimeManager::start(TimeLimits::unlimited())
+        } else {
+            TimeManager::start(self.time_limits)
+        };
+        self.last_info = Instant::now();
+        self.nodes = 0;
+        self.killers = [[None; 2]; MAX_PLY];
+        *self.history_scores = [[[0; 64]; 64]; 2];
+
+        let mut board = *board;
+        let mut result = SearchResult {
+            depth: 0,
+            score: 0,
+            pv: Vec::new(),
+        };
+
+        for depth in 1..=max_depth.clamp(1, MAX_DEPTH) {
+            self.depth = depth;
+            self.seldepth = 0;
+            self.excluded_root_moves.clear();
+
+            // Each further line searches the root without the best moves of the earlier lines
+            let mut first_line = None;
+            for line in 0..self.multi_pv {
+                let mut pv = Vec::new();
+                self.follow_pv = line == 0;
+                let score = self.negamax(&mut board, depth, 0, NEGINF, INF, &mut pv);
+                if self.stopped || (line > 0 && pv.is_empty()) {
+                    // Incomplete, or no root moves left
+                    break;
                 }
-                let score = b.minimax(depth - 1, alpha, beta).0;
 
-                if score >= beta {
-                    return (beta, None); // fail hard beta-cutoff
+                self.report(line + 1, Some(score), &pv);
+                self.excluded_root_moves.extend(pv.first());
+                if line == 0 {
+                    first_line = Some((score, pv));
                 }
+            }
+
+            // An incomplete first line means an incomplete iteration, rely on the previous one
+            let (score, pv) = match first_line {
+                Some(line) => line,
+                None => break,
+            };
+            self.prev_pv.clone_from(&pv);
+            result = SearchResult { depth, score, pv };
+
+            self.check_ponderhit();
+            if self.stopped || self.stop.load(Ordering::Relaxed) || self.time.soft_limit_reached() {
+                break;
+            }
+        }
+
+        result
+    }
+
+    /// Start the clock when the pondered move is played
+    fn check_ponderhit(&mut self) {
+        let hit = match &self.ponderhit {
+            Some(ponderhit) => self.pondering && ponderhit.load(Ordering::Relaxed),
+            None => false,
+        };
+        if hit {
+            self.pondering = false;
+            self.time = TimeManager::start(self.time_limits);
+        }
+    }
+
+    #[inline]
+    fn should_stop(&mut self) -> bool {
+        if self.nodes & (STOP_CHECK_INTERVAL - 1) == 0 {
+            self.check_ponderhit();
+        }
+
+        // The first iteration has to complete so that there is a move to play
+        if !self.stopped
+            && !self.prev_pv.is_empty()
+            && self.nodes & (STOP_CHECK_INTERVAL - 1) == 0
+            && (self.stop.load(Ordering::Relaxed) || self.time.hard_limit_reached())
+        {
+            self.stopped = true;
+        }
+
+        if self.nodes & (STOP_CHECK_INTERVAL - 1) == 0
+            && self.info_callback.is_some()
+            && self.last_info.elapsed() >= INFO_INTERVAL
+        {
+            self.report(1, None, &[]);
+        }
+
+        self.stopped
+    }
+
+    fn report(&mut self, multipv: usize, score: Option<i32>, pv: &[Move]) {
+        let info = SearchInfo {
+            depth: self.depth,
+            multipv,
+            seldepth: self.seldepth,
+            score,
+            nodes: self.nodes,
+            time: self.time.elapsed(),
+            pv,
+            hashfull: self.tt.hashfull(),
+        };
+        if let Some(callback) = self.info_callback.as_mut() {
+            callback(&info);
+        }
+        self.last_info = Instant::now();
+    }
+
+    /// Fail-soft negamax with principal variation search, scores are from the side to move's
+    /// perspective https://www.chessprogramming.org/Principal_Variation_Search
+    fn negamax(
+        &mut self,
+        board: &mut Board,
+        depth: usize,
+        ply: usize,
+        mut alpha: i32,
+        beta: i32,
+        pv: &mut Vec<Move>,
+    ) -> i32 {
+        let white = board.white_to_move();
+        self.nodes += 1;
+        self.seldepth = self.seldepth.max(ply);
+        pv.clear();
+        let skip_null_move = std::mem::replace(&mut self.skip_null_move, false);
 
-                if best.is_none() || score > best.unwrap().0 {
-                    alpha = score;
-                    best = Some((score, *m));
+        if self.should_stop() {
+            return 0;
+        }
+
+        let key = board.zobrist();
+        // The root is searched to return a move even if the game is drawn
+        if ply > 0 && self.is_draw(board, key) {
+            return 0;
+        }
+
+        if depth == 0 {
+            return self.quiescence(board, ply, alpha, beta);
+        }
+
+        let mut pv_move = None;
+        if self.follow_pv && ply < self.prev_pv.len() {
+            pv_move = Some(self.prev_pv[ply]);
+        }
+
+        let mut hash_move = None;
+        if let Some(entry) = self.tt.probe(key) {
+            hash_move = entry.best_move;
+            // Always search the root, so that a move is returned
+            if ply > 0 && entry.depth as usize >= depth {
+                let score = score_from_tt(entry.score, ply);
+                match entry.bound {
+                    Bound::Exact => {
+                        pv.extend(entry.best_move);
+                        return score;
+                    }
+                    Bound::Lower if score >= beta => return score,
+                    Bound::Upper if score <= alpha => return score,
+                    _ => {}
                 }
             }
-        } else {
-            for m in moves.iter() {
-                let b = self.make_move(m);
-                if b.is_in_check(white) {
-                    // Illegal, can't move into check
-                    continue;
+        }
+
+        let in_check = board.is_in_check(white);
+        // Pruning is only done in null window nodes, and never when in check
+        let prunable = !in_check && ply > 0 && beta == alpha + 1;
+        let static_eval = board.relative_eval();
+        if prunable {
+            if self.features.reverse_futility_pruning
+                && depth <= FUTILITY_DEPTH
+                && !is_mate_score(beta)
+                && static_eval - REVERSE_FUTILITY_MARGIN * depth as i32 >= beta
+            {
+                return static_eval;
+            }
+
+            if !skip_null_move {
+                if let Some(score) = self.null_move_search(board, depth, ply, beta, static_eval) {
+                    return score;
                 }
-                let score = b.minimax(depth - 1, alpha, beta).0;
+            }
+        }
+
+        // Even a good quiet move is not expected to raise the score to alpha this close to the
+        // leaves
+        let futility_value = static_eval + FUTILITY_MARGIN * depth as i32;
+        let futile = prunable
+            && self.features.futility_pruning
+            && depth <= FUTILITY_DEPTH
+            && !is_mate_score(alpha)
+            && futility_value <= alpha;
+        let mut pruned = false;
+
+        let mut moves = board.generate_legal_moves();
+        self.order_moves(board, &mut moves, ply, pv_move, hash_move);
+
+        let original_alpha = alpha;
+        let mut best: Option<(i32, Move)> = None;
+        let mut child_pv = Vec::new();
+        let mut following_pv = pv_move.is_some();
+        let mut move_number = 0;
+
+        for m in moves.iter() {
+            if ply == 0 && self.excluded_root_moves.contains(m) {
+                continue;
+            }
+            move_number += 1;
+
+            self.history.push(key);
+            let undo = board.make_move_in_place(m);
 
-                if score <= alpha {
-                    return (alpha, None); // fail hard alpha-cutoff
+            // Quiet moves that don't give check, the first move is always searched in full so that
+            // mates and stalemates are found
+            let reducible = best.is_some()
+                && !m.is_capture()
+                && !m.is_promotion()
+                && !board.is_in_check(!white);
+            if reducible && futile {
+                board.unmake_move(m, &undo);
+                self.history.pop();
+                pruned = true;
+                continue;
+            }
+            let reduction = if reducible
+                && !in_check
+                && self.features.late_move_reductions
+                && move_number > LMR_FULL_DEPTH_MOVES
+                && depth >= LMR_MIN_DEPTH
+            {
+                if depth >= LMR_DEEP && move_number > LMR_DEEP {
+                    2
+                } else {
+                    1
                 }
+            } else {
+                0
+            };
+
+            // Only the first move of a PV node can continue the previous PV
+            self.follow_pv = following_pv && pv_move == Some(*m);
+            following_pv = false;
 
-                if best.is_none() || score < best.unwrap().0 {
-                    beta = score;
-                    best = Some((score, *m));
+            let score = if best.is_none() {
+                -self.negamax(board, depth - 1, ply + 1, -beta, -alpha, &mut child_pv)
+            } else {
+                // Later moves are expected to be worse than the first. A null window search
+                // proves that cheaply, and only moves that beat alpha are searched again with
+                // the full window. Late quiet moves are first searched at reduced depth, and
+                // again at full depth if they beat alpha.
+                let scout_beta = if self.features.pvs { alpha + 1 } else { beta };
+                let mut score = -self.negamax(
+                    board,
+                    depth - 1 - reduction,
+                    ply + 1,
+                    -scout_beta,
+                    -alpha,
+                    &mut child_pv,
+                );
+                if reduction > 0 && score > alpha {
+                    score = -self.negamax(
+                        board,
+                        depth - 1,
+                        ply + 1,
+                        -scout_beta,
+                        -alpha,
+                        &mut child_pv,
+                    );
                 }
+                if self.features.pvs && score > alpha && score < beta {
+                    -self.negamax(board, depth - 1, ply + 1, -beta, -alpha, &mut child_pv)
+                } else {
+                    score
+                }
+            };
+            board.unmake_move(m, &undo);
+            self.history.pop();
+            if self.stopped {
+                return 0;
             }
+
+            if best.is_none_or(|(best_score, _)| score > best_score) {
+                best = Some((score, *m));
+                update_pv(pv, *m, &child_pv);
+            }
+            if score >= beta {
+                self.record_cutoff(m, white, depth, ply);
+                self.store(key, depth, ply, Bound::Lower, score, Some(*m));
+                return score;
+            }
+            alpha = alpha.max(score);
         }
 
-        if let Some((eval, m)) = best {
-            (eval, Some(m))
-        } else {
-            if self.is_in_check(white) {
-                // Mated
-                let depth_penalty = 50 - (depth as i32);
-                return if white {
-                    (NEGINF + depth_penalty, None)
+        match best {
+            Some((score, m)) => {
+                // A pruned move could have scored up to the futility value, which is still at
+                // most alpha, so the upper bound holds
+                let score = if pruned {
+                    score.max(futility_value)
+                } else {
+                    score
+                };
+                let bound = if score > original_alpha {
+                    Bound::Exact
                 } else {
-                    (INF - depth_penalty, None)
+                    Bound::Upper
                 };
+                self.store(key, depth, ply, bound, score, Some(m));
+                score
             }
-
+            // Mated, prefer the shortest mate
+            None if in_check => -(MATE - ply as i32),
             // Stalemate
-            (0, None)
+            None => 0,
         }
     }
-}
 
-fn order_moves(moves: &mut [Move]) {
-    moves.sort_unstable_by_key(|m| -score_move(m))
-}
+    /// Let the opponent move twice with a reduced search, if that still fails high a real move
+    /// surely would too. Only tried in null window nodes out of check, not right after another
+    /// null move, and not when the side to move has only pawns, where zugzwang is common.
+    /// Returns the score to prune with.
+    fn null_move_search(
+        &mut self,
+        board: &mut Board,
+        depth: usize,
+        ply: usize,
+        beta: i32,
+        static_eval: i32,
+    ) -> Option<i32> {
+        let pieces = non_pawn_pieces(board, board.white_to_move());
+        if !self.features.null_move
+            || depth < NULL_MOVE_MIN_DEPTH
+            || pieces == 0
+            || is_mate_score(beta)
+            || static_eval < beta
+        {
+            return None;
+        }
+
+        // Adaptive null move pruning, deeper searches afford a larger reduction
+        let reduction = if depth > NULL_MOVE_DEEP { 3 } else { 2 };
+
+        let key = board.zobrist();
+        self.history.push(key);
+        let undo = board.make_null_move();
+        self.follow_pv = false;
+        self.skip_null_move = true;
+        let mut child_pv = Vec::new();
+        let score = -self.negamax(
+            board,
+            depth.saturating_sub(1 + reduction),
+            ply + 1,
+            -beta,
+            -beta + 1,
+            &mut child_pv,
+        );
+        board.unmake_null_move(&undo);
+        self.history.pop();
+        if self.stopped || score < beta {
+            return None;
+        }
+
+        if self.features.null_move_verification && pieces <= ZUGZWANG_PIECES {
+            // Search the node itself without a null move at the reduced depth
+            self.skip_null_move = true;
+            let verified =
+                self.negamax(board, depth - reduction, ply, beta - 1, beta, &mut child_pv);
+            if self.stopped || verified < beta {
+                return None;
+            }
+        }
+
+        // Passing can't prove a mate
+        Some(if is_mate_score(score) { beta } else { score })
+    }
 
-fn score_move(m: &Move) -> i32 {
-    if m.is_promotion() {
-        return 10;
+    fn order_moves(
+        &self,
+        board: &Board,
+        moves: &mut [Move],
+        ply: usize,
+        pv_move: Option<Move>,
+        hash_move: Option<Move>,
+    ) {
+        moves.sort_by_cached_key(|m| -self.score_move(board, m, ply, pv_move, hash_move))
     }
-    if m.is_capture() {
-        return 5;
+
+    fn score_move(
+        &self,
+        board: &Board,
+        m: &Move,
+        ply: usize,
+        pv_move: Option<Move>,
+        hash_move: Option<Move>,
+    ) -> i32 {
+        if Some(*m) == pv_move {
+            return PV_MOVE_SCORE;
+        }
+        if Some(*m) == hash_move {
+            return HASH_MOVE_SCORE;
+        }
+        if m.is_capture() || is_queen_promotion(m) {
+            let loss = capture_loss(board, m);
+            if loss < 0 {
+                // After the quiet moves, the least losing first
+                return loss;
+            }
+            return CAPTURE_SCORE + capture_score(board, m);
+        }
+        if !self.features.quiet_move_ordering {
+            return 0;
+        }
+        if let Some(slot) = self.killers[ply].iter().position(|k| *k == Some(*m)) {
+            return KILLER_SCORE - slot as i32;
+        }
+        self.history_scores[board.white_to_move() as usize][m.from() as usize][m.to() as usize]
     }
 
-    // TODO from/to kind
+    /// Remember quiet moves that refute the opponent's move, to try them early in similar
+    /// positions
+    fn record_cutoff(&mut self, m: &Move, white: bool, depth: usize, ply: usize) {
+        if m.is_capture() || m.is_promotion() || !self.features.quiet_move_ordering {
+            return;
+        }
+
+        let killers = &mut self.killers[ply];
+        if killers[0] != Some(*m) {
+            killers[1] = killers[0];
+            killers[0] = Some(*m);
+        }
 
-    0
+        let side = &mut self.history_scores[white as usize];
+        let score = &mut side[m.from() as usize][m.to() as usize];
+        *score += (depth * depth) as i32;
+        if *score >= MAX_HISTORY_SCORE {
+            for score in side.iter_mut().flatten() {
+                *score /= 2;
+            }
+        }
+    }
+
+    /// Repetitions and the fifty-move rule. A position is a draw already at its first repetition,
+    /// as the side that allowed it could repeat it again.
+    fn is_draw(&self, board: &Board, key: u64) -> bool {
+        let halfmove_clock = board.get_halfmove_clock();
+        if halfmove_clock >= FIFTY_MOVE_HALFMOVES {
+            // A checkmate on the last move still ends the game
+            return !board.is_in_check(board.white_to_move())
+                || !board.generate_legal_moves().is_empty();
+        }
+
+        repetition_candidates(&self.history, halfmove_clock).any(|k| *k == key)
+    }
+
+    /// Search captures and promotions until the position is quiet, so that the static evaluation
+    /// is not taken in the middle of an exchange https://www.chessprogramming.org/Quiescence_Search
+    /// Positions in check are never quiet, all evasions are searched.
+    fn quiescence(&mut self, board: &mut Board, ply: usize, mut alpha: i32, beta: i32) -> i32 {
+        let white = board.white_to_move();
+        self.nodes += 1;
+        self.seldepth = self.seldepth.max(ply);
+
+        if self.should_stop() {
+            return 0;
+        }
+
+        if ply >= MAX_PLY {
+            return board.relative_eval();
+        }
+
+        // In check there is no quiet alternative to the captures, all evasions are searched
+        let in_check = board.is_in_check(white);
+        let mut moves;
+        let mut best;
+        if in_check {
+            moves = board.generate_legal_moves();
+            if moves.is_empty() {
+                return -(MATE - ply as i32);
+            }
+            best = NEGINF;
+        } else {
+            // Stand pat: the side to move can usually do at least as well as the static
+            // evaluation by not capturing
+            let stand_pat = board.relative_eval();
+            if stand_pat >= beta {
+                return stand_pat;
+            }
+            alpha = alpha.max(stand_pat);
+            moves = board.generate_pseudo_legal_captures();
+            best = stand_pat;
+        }
+        moves.sort_by_cached_key(|m| -capture_score(board, m));
+
+        for m in moves.iter() {
+            if !in_check && capture_loss(board, m) < 0 {
+                // Losing captures are unlikely to improve on standing pat
+                continue;
+            }
+
+            let undo = board.make_move_in_place(m);
+            if board.is_in_check(white) {
+                // Illegal, can't move into check
+                board.unmake_move(m, &undo);
+                continue;
+            }
+
+            let score = -self.quiescence(board, ply + 1, -beta, -alpha);
+            board.unmake_move(m, &undo);
+            if self.stopped {
+                return 0;
+            }
+
+            if score >= beta {
+                return score;
+            }
+            best = best.max(score);
+            alpha = alpha.max(score);
+        }
+
+        best
+    }
+
+    #[inline]
+    fn store(
+        &self,
+        key: u64,
+        depth: usize,
+        ply: usize,
+        bound: Bound,
+        score: i32,
+        best_move: Option<Move>,
+    ) {
+        if ply == 0 && !self.excluded_root_moves.is_empty() {
+            // The root result is only the best of the remaining moves
+            return;
+        }
+        self.tt
+            .store(key, depth, bound, score_to_tt(score, ply), best_move);
+    }
+}
+
+#[inline]
+fn update_pv(pv: &mut Vec<Move>, m: Move, child_pv: &[Move]) {
+    pv.clear();
+    pv.push(m);
+    pv.extend_from_slice(child_pv);
+}
+
+fn is_queen_promotion(m: &Move) -> bool {
+    m.is_promotion() && m.flags_nibble() & 0b11 == 0b11
+}
+
+/// Knights, bishops, rooks and queens of the side
+fn non_pawn_pieces(board: &Board, white: bool) -> u32 {
+    let bb = &board.bitboard;
+    let pieces = if white {
+        bb.white_knights | bb.white_bishoplike | bb.white_rooklike
+    } else {
+        bb.black_knights | bb.black_bishoplike | bb.black_rooklike
+    };
+    pieces.popcnt() as u32
+}
+
+/// Most valuable victim first, and of those the least valuable attacker first
+/// https://www.chessprogramming.org/MVV-LVA
+fn capture_score(board: &Board, m: &Move) -> i32 {
+    let promotion = if is_queen_promotion(m) { QUEEN } else { 0 };
+    let victim = if m.is_capture() {
+        piece_value(board.kind_at(m.to()))
+    } else {
+        0
+    };
+    8 * (victim + promotion) - attacker_rank(board.kind_at(m.from()))
+}
+
+/// Attackers in order of value, the king's value would outweigh the victim
+fn attacker_rank(kind: PieceKind) -> i32 {
+    piece_value(kind).min(QUEEN + PAWN) / PAWN
+}
+
+/// Material lost by a capture according to SEE, 0 if it doesn't lose material. Taking a piece
+/// worth at least the capturing piece can't lose material, so SEE is skipped for those.
+fn capture_loss(board: &Board, m: &Move) -> i32 {
+    if piece_value(board.kind_at(m.from())) <= piece_value(board.kind_at(m.to())) {
+        return 0;
+    }
+    board.see(m).min(0)
 }
@@ -0,0 +1,116 @@
+use bitintr::Tzcnt;
+
+use crate::board::Board;
+use crate::constants::intersects;
+use crate::eval::{piece_value, BISHOP, KING, KNIGHT, PAWN, QUEEN, ROOK};
+use crate::move_representation::Move;
+
+/// Longest possible exchange, every piece on the board capturing on the square
+const MAX_EXCHANGE: usize = 64;
+
+impl Board {
+    /// Static exchange evaluation https://www.chessprogramming.org/Static_Exchange_Evaluation
+    ///
+    /// Material the side to move wins with the move, when both sides keep recapturing on the
+    /// target square with their least valuable piece for as long as it pays off. Sliders behind
+    /// the capturing pieces join in as the pieces in front of them leave. Pawns reaching the last
+    /// row in the middle of an exchange are not counted as promoting.
+    pub fn see(&self, mov: &Move) -> i32 {
+        let to = mov.to();
+        let mut occupancy = self.bitboard.coverage() & !(1 << mov.from());
+
+        let mut gain = [0; MAX_EXCHANGE];
+        let mut attacker_value = self.piece_value_at(mov.from());
+        if mov.flags_nibble() == 0b0101 {
+            // En passant, the captured pawn is beside the target square
+            gain[0] = PAWN;
+            let captured = if self.white_to_move() { to - 8 } else { to + 8 };
+            occupancy &= !(1 << captured);
+        } else if mov.is_capture() {
+            gain[0] = self.piece_value_at(to);
+        }
+        if mov.is_promotion() {
+            attacker_value = match mov.flags_nibble() & 0b11 {
+                0b00 => KNIGHT,
+                0b01 => BISHOP,
+                0b10 => ROOK,
+                _ => QUEEN,
+            };
+            gain[0] += attacker_value - PAWN;
+        }
+
+        let mut white = !self.white_to_move();
+        let mut depth = 0;
+        loop {
+            depth += 1;
+            // Speculative score if the piece that just moved is captured
+            gain[depth] = attacker_value - gain[depth - 1];
+            if (-gain[depth - 1]).max(gain[depth]) < 0 {
+                // Neither continuing nor stopping here changes the outcome
+                break;
+            }
+
+            // Pieces that already captured are removed from occupancy
+            let attackers = self.attackers_to(to, occupancy, white) & occupancy;
+            let (square, value) = match self.least_valuable_attacker(attackers, white) {
+                Some(attacker) => attacker,
+                None => break,
+            };
+            occupancy &= !(1 << square);
+            if value == KING && intersects(self.attackers_to(to, occupancy, !white), occupancy) {
+                // The king can't capture a defended piece
+                break;
+            }
+
+            attacker_value = value;
+            white = !white;
+        }
+
+        // The last speculative score never happened, the side to move at each step chooses
+        // between stopping and capturing
+        while depth > 1 {
+            depth -= 1;
+            gain[depth - 1] = -(-gain[depth - 1]).max(gain[depth]);
+        }
+        gain[0]
+    }
+
+    /// Square and value of the side's cheapest piece among the attackers
+    fn least_valuable_attacker(&self, attackers: u64, white: bool) -> Option<(u8, i32)> {
+        let bb = &self.bitboard;
+        let (pawns, knights, rooklike, bishoplike, king) = if white {
+            (
+                bb.white_pawns,
+                bb.white_knights,
+                bb.white_rooklike,
+                bb.white_bishoplike,
+                bb.white_king,
+            )
+        } else {
+            (
+                bb.black_pawns,
+                bb.black_knights,
+                bb.black_rooklike,
+                bb.black_bishoplike,
+                bb.black_king,
+            )
+        };
+
+        [
+            (pawns, PAWN),
+            (knights, KNIGHT),
+            (bishoplike & !rooklike, BISHOP),
+            (rooklike & !bishoplike, ROOK),
+            (rooklike & bishoplike, QUEEN),
+            (king, KING),
+        ]
+        .iter()
+        .map(|(pieces, value)| (pieces & attackers, *value))
+        .find(|(pieces, _)| *pieces != 0)
+        .map(|(pieces, value)| (pieces.tzcnt() as u8, value))
+    }
+
+    fn piece_value_at(&self, pos_tzcnt: u8) -> i32 {
+        piece_value(self.kind_at(pos_tzcnt))
+    }
+}
@@ -0,0 +1,151 @@
+use std::time::{Duration, Instant};
+
+use vampirc_uci::UciTimeControl;
+
+/// Time reserved for communication with the GUI, subtracted from every time budget
+pub const DEFAULT_MOVE_OVERHEAD: Duration = Duration::from_millis(30);
+
+/// Assumed number of moves left in the game when the GUI doesn't send movestogo
+const DEFAULT_MOVES_TO_GO: u32 = 30;
+
+/// How many times the soft limit a single move may use when the search is unstable
+const HARD_LIMIT_FACTOR: u32 = 4;
+
+/// The search stops starting new iterations after the soft limit, and aborts the running
+/// iteration at the hard limit. No limit means the search runs until stopped or depth is reached.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct TimeLimits {
+    pub soft: Option<Duration>,
+    pub hard: Option<Duration>,
+}
+
+impl TimeLimits {
+    pub const fn unlimited() -> Self {
+        TimeLimits {
+            soft: None,
+            hard: None,
+        }
+    }
+
+    /// Use exactly the given time, minus overhead
+    pub fn move_time(time: Duration, move_overhead: Duration) -> Self {
+        let time = usable_time(time, move_overhead);
+        TimeLimits {
+            soft: Some(time),
+            hard: Some(time),
+        }
+    }
+
+    /// Budget for one move when we have time_left on the clock, gain increment per move and
+    /// have to make moves_to_go moves before the next time control
+    pub fn from_clock(
+        time_left: Duration,
+        increment: Duration,
+        moves_to_go: Option<u32>,
+        move_overhead: Duration,
+    ) -> Self {
+        let available = usable_time(time_left, move_overhead);
+        let moves_to_go = moves_to_go.unwrap_or(DEFAULT_MOVES_TO_GO).max(1);
+
+        // Never plan to use more than most of the remaining clock on a single move
+        let max = if moves_to_go == 1 {
+            available * 9 / 10
+        } else {
+            available / 2
+        };
+        let soft = (available / moves_to_go + increment * 3 / 4).min(max);
+        let hard = (soft * HARD_LIMIT_FACTOR).min(max);
+
+        TimeLimits {
+            soft: Some(soft),
+            hard: Some(hard),
+        }
+    }
+
+    /// None when the clock of the side to move is left out, which is no time control at all
+    pub fn from_uci(
+        time_control: &UciTimeControl,
+        white_to_move: bool,
+        move_overhead: Duration,
+    ) -> Option<Self> {
+        match time_control {
+            UciTimeControl::Ponder | UciTimeControl::Infinite => Some(TimeLimits::unlimited()),
+            UciTimeControl::MoveTime(time) => {
+                Some(TimeLimits::move_time(to_std_duration(time), move_overhead))
+            }
+            UciTimeControl::TimeLeft {
+                white_time,
+                black_time,
+                white_increment,
+                black_increment,
+                moves_to_go,
+            } => {
+                let (time, increment) = if white_to_move {
+                    (white_time, white_increment)
+                } else {
+                    (black_time, black_increment)
+                };
+
+                time.as_ref().map(|time| {
+                    TimeLimits::from_clock(
+                        to_std_duration(time),
+                        increment.as_ref().map(to_std_duration).unwrap_or_default(),
+                        moves_to_go.map(|m| m as u32),
+                        move_overhead,
+                    )
+                })
+            }
+        }
+    }
+
+    pub fn is_limited(&self) -> bool {
+        self.hard.is_some() || self.soft.is_some()
+    }
+}
+
+/// Keeps track of elapsed search time against the limits
+#[derive(Copy, Clone, Debug)]
+pub struct TimeManager {
+    start: Instant,
+    limits: TimeLimits,
+}
+
+impl TimeManager {
+    pub fn start(limits: TimeLimits) -> Self {
+        TimeManager {
+            start: Instant::now(),
+            limits,
+        }
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    /// Whether the running iteration has to be aborted
+    pub fn hard_limit_reached(&self) -> bool {
+        match self.limits.hard {
+            Some(hard) => self.elapsed() >= hard,
+            None => false,
+        }
+    }
+
+    /// Whether a new iteration should be started
+    pub fn soft_limit_reached(&self) -> bool {
+        match self.limits.soft {
+            Some(soft) => self.elapsed() >= soft,
+            None => false,
+        }
+    }
+}
+
+/// Keep at least a millisecond, so that the search can always complete its first iteration
+fn usable_time(time: Duration, move_overhead: Duration) -> Duration {
+    time.checked_sub(move_overhead)
+        .unwrap_or_default()
+        .max(Duration::from_millis(1))
+}
+
+fn to_std_duration(duration: &time::Duration) -> Duration {
+    duration.to_std().unwrap_or_default()
+}
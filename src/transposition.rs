@@ -0,0 +1,208 @@
+use std::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+
+use crate::eval::{MATE, MAX_PLY};
+use crate::move_representation::Move;
+
+pub const DEFAULT_TT_SIZE_MB: usize = 16;
+
+/// Age is stored in 6 bits of the entry
+const AGE_MASK: u8 = 0x3F;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Bound {
+    /// The score is exact
+    Exact,
+    /// The search failed high, the real score is at least the stored score
+    Lower,
+    /// The search failed low, the real score is at most the stored score
+    Upper,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct TtEntry {
+    pub depth: u8,
+    pub bound: Bound,
+    pub score: i32,
+    pub best_move: Option<Move>,
+    age: u8,
+}
+
+/// Bit overview of entry data:
+/// 0-15: best move (0 = no move)
+/// 16-23: depth
+/// 24-25: bound (0 = empty slot, 1 = exact, 2 = lower, 3 = upper)
+/// 26-31: age
+/// 32-63: score
+///
+/// The key is stored xor'ed with the data so that torn writes from concurrent searches are
+/// detected as key mismatches https://www.chessprogramming.org/Shared_Hash_Table#Lockless
+#[derive(Default)]
+struct Slot {
+    key_xor_data: AtomicU64,
+    data: AtomicU64,
+}
+
+/// Fixed-size, power-of-two transposition table https://www.chessprogramming.org/Transposition_Table
+pub struct TranspositionTable {
+    slots: Vec<Slot>,
+    mask: usize,
+    age: AtomicU8,
+}
+
+impl TranspositionTable {
+    pub fn new(size_mb: usize) -> Self {
+        let n_slots = slot_count(size_mb);
+        let mut slots = Vec::with_capacity(n_slots);
+        slots.resize_with(n_slots, Slot::default);
+
+        TranspositionTable {
+            slots,
+            mask: n_slots - 1,
+            age: AtomicU8::new(0),
+        }
+    }
+
+    pub fn resize(&mut self, size_mb: usize) {
+        *self = TranspositionTable::new(size_mb);
+    }
+
+    pub fn clear(&mut self) {
+        for slot in self.slots.iter_mut() {
+            *slot.key_xor_data.get_mut() = 0;
+            *slot.data.get_mut() = 0;
+        }
+        *self.age.get_mut() = 0;
+    }
+
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    /// Permille of the table used by the current search, estimated from the first entries
+    pub fn hashfull(&self) -> usize {
+        let age = self.age.load(Ordering::Relaxed);
+        let sample = &self.slots[..self.slots.len().min(1000)];
+        let used = sample
+            .iter()
+            .filter_map(|slot| unpack(slot.data.load(Ordering::Relaxed)))
+            .filter(|entry| entry.age == age)
+            .count();
+        used * 1000 / sample.len()
+    }
+
+    /// Mark the start of a new search, so that entries from earlier moves are replaced first.
+    /// Called once before starting the threads of a search, so that the age stays the same
+    /// while they run.
+    pub fn new_search(&mut self) {
+        let age = self.age.get_mut();
+        *age = (*age + 1) & AGE_MASK;
+    }
+
+    pub fn probe(&self, key: u64) -> Option<TtEntry> {
+        let slot = &self.slots[key as usize & self.mask];
+        let data = slot.data.load(Ordering::Relaxed);
+        let key_xor_data = slot.key_xor_data.load(Ordering::Relaxed);
+        if key_xor_data ^ data != key {
+            return None;
+        }
+
+        unpack(data)
+    }
+
+    pub fn store(&self, key: u64, depth: usize, bound: Bound, score: i32, best_move: Option<Move>) {
+        let slot = &self.slots[key as usize & self.mask];
+        let age = self.age.load(Ordering::Relaxed);
+        let depth = depth.min(u8::MAX as usize) as u8;
+
+        let old_data = slot.data.load(Ordering::Relaxed);
+        let old_key = slot.key_xor_data.load(Ordering::Relaxed) ^ old_data;
+        let mut best_move = best_move;
+
+        if let Some(old) = unpack(old_data) {
+            let same_position = old_key == key;
+            // Prefer keeping deep entries from the current search
+            if !same_position && old.age == age && old.depth > depth && bound != Bound::Exact {
+                return;
+            }
+            if same_position && best_move.is_none() {
+                best_move = old.best_move;
+            }
+        }
+
+        let data = pack(depth, bound, score, best_move, age);
+        slot.key_xor_data.store(key ^ data, Ordering::Relaxed);
+        slot.data.store(data, Ordering::Relaxed);
+    }
+}
+
+fn slot_count(size_mb: usize) -> usize {
+    let bytes = size_mb.max(1) * 1024 * 1024;
+    let slots = bytes / std::mem::size_of::<Slot>();
+    // Round down to a power of two so that indexing is a mask
+    1 << (usize::BITS - 1 - slots.leading_zeros())
+}
+
+fn pack(depth: u8, bound: Bound, score: i32, best_move: Option<Move>, age: u8) -> u64 {
+    let move_bits = best_move.map(|m| m.to_u16()).unwrap_or(0) as u64;
+    let bound_bits: u64 = match bound {
+        Bound::Exact => 1,
+        Bound::Lower => 2,
+        Bound::Upper => 3,
+    };
+
+    move_bits
+        | (depth as u64) << 16
+        | bound_bits << 24
+        | ((age & AGE_MASK) as u64) << 26
+        | (score as u32 as u64) << 32
+}
+
+fn unpack(data: u64) -> Option<TtEntry> {
+    let bound = match (data >> 24) & 0b11 {
+        0 => return None,
+        1 => Bound::Exact,
+        2 => Bound::Lower,
+        3 => Bound::Upper,
+        _ => unreachable!(),
+    };
+    let move_bits = (data & 0xFFFF) as u16;
+
+    Some(TtEntry {
+        depth: (data >> 16) as u8,
+        bound,
+        score: (data >> 32) as u32 as i32,
+        best_move: if move_bits == 0 {
+            None
+        } else {
+            Some(Move::from_u16(move_bits))
+        },
+        age: ((data >> 26) as u8) & AGE_MASK,
+    })
+}
+
+/// Mate scores are relative to the root, but stored relative to the node so that they stay
+/// correct when the position is reached at another ply. Bounds inherited from the root may be
+/// closer mates than possible from the node, these are clamped to the mate score range.
+pub fn score_to_tt(score: i32, ply: usize) -> i32 {
+    if score >= MATE - MAX_PLY as i32 {
+        score.saturating_add(ply as i32).min(MATE)
+    } else if score <= -MATE + MAX_PLY as i32 {
+        score.saturating_sub(ply as i32).max(-MATE)
+    } else {
+        score
+    }
+}
+
+pub fn score_from_tt(score: i32, ply: usize) -> i32 {
+    if score >= MATE - MAX_PLY as i32 {
+        score - ply as i32
+    } else if score <= -MATE + MAX_PLY as i32 {
+        score + ply as i32
+    } else {
+        score
+    }
+}
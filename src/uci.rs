@@ -1,144 +1,419 @@
+use std::fmt;
 use std::io::{self, BufRead, BufReader, Result};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, Scope, ScopedJoinHandle};
+use std::time::Duration;
 
-use vampirc_uci::{UciFen, UciMessage, UciMove, UciSearchControl, UciSquare, UciTimeControl};
+use vampirc_uci::{UciFen, UciMessage, UciMove, UciSearchControl, UciTimeControl};
 
 use crate::{
-    board::{Board, PieceKind},
-    constants::{self, intersects, SQUARE_NAME},
-    fen,
-    move_representation::Move,
+    board::Board,
+    eval::mate_in_moves,
+    fen::{self, FenError},
+    game::Game,
+    search::{Search, SearchFeatures, SearchInfo, MAX_DEPTH},
+    time_management::{TimeLimits, DEFAULT_MOVE_OVERHEAD},
+    transposition::{TranspositionTable, DEFAULT_TT_SIZE_MB},
+    uci_options::{
+        find_option, OptionValue, DEBUG, FUTILITY_PRUNING, HASH, LATE_MOVE_REDUCTIONS,
+        MOVE_OVERHEAD, MULTI_PV, NULL_MOVE, NULL_MOVE_VERIFICATION, OPTIONS, PVS,
+        QUIET_MOVE_ORDERING, REVERSE_FUTILITY_PRUNING, THREADS,
+    },
 };
 
 pub struct UciState {
     debug: bool,
-    board: Option<Board>,
+    /// The position with the moves leading to it, for repetition detection
+    game: Option<Game>,
+    /// Shared with the search thread, only resized or cleared while no search is running
+    tt: Arc<TranspositionTable>,
+    move_overhead: Duration,
+    /// Searching threads, helper threads share the transposition table with the main search
+    threads: usize,
+    multi_pv: usize,
+    features: SearchFeatures,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum UciError {
+    InvalidFen(String, FenError),
+    /// position with neither startpos nor fen
+    MissingPosition,
+    IllegalMove(String),
+    /// go without a preceding position
+    NoPosition,
+    /// A search thread still holds the transposition table
+    TableInUse,
+    UnknownCommand(String),
+    /// A message that should only be sent from the engine to the GUI
+    UnexpectedMessage(String),
+    Unsupported(&'static str),
+    UnknownOption(String),
+    InvalidOptionValue(String),
+}
+
+impl fmt::Display for UciError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UciError::InvalidFen(fen, e) => write!(f, "Invalid FEN string '{}': {}", fen, e),
+            UciError::MissingPosition => write!(f, "Expected startpos or fen in position"),
+            UciError::IllegalMove(m) => write!(f, "Illegal move {}", m),
+            UciError::NoPosition => write!(f, "No position set"),
+            UciError::TableInUse => write!(f, "The hash table is in use by a search"),
+            UciError::UnknownCommand(text) => write!(f, "Unknown command '{}'", text),
+            UciError::UnexpectedMessage(text) => write!(f, "Unexpected message '{}'", text),
+            UciError::Unsupported(command) => write!(f, "{} is not supported", command),
+            UciError::UnknownOption(name) => write!(f, "Unknown option '{}'", name),
+            UciError::InvalidOptionValue(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for UciError {}
+
+/// Search depth for a go command without depth or time control
+const DEFAULT_GO_DEPTH: usize = 6;
+
+/// A search running on a worker thread, which writes bestmove when it finishes
+struct SearchThread<'scope> {
+    handle: ScopedJoinHandle<'scope, ()>,
+    stop: Arc<AtomicBool>,
+    /// Set when the pondered move is played, the search then continues under the time control
+    /// given with go ponder
+    ponderhit: Arc<AtomicBool>,
+    infinite: bool,
+}
+
+impl SearchThread<'_> {
+    /// Make the search return its best move as soon as possible, and wait for it
+    fn stop(self, output: &Mutex<impl io::Write>) -> Result<()> {
+        self.stop.store(true, Ordering::Relaxed);
+        self.handle.thread().unpark();
+        self.wait(output)
+    }
+
+    /// Infinite searches, and ponder searches before the ponder hit, only finish when stopped
+    fn waits_for_stop(&self) -> bool {
+        self.infinite || !self.ponderhit.load(Ordering::Relaxed)
+    }
+
+    fn ponderhit(&self) {
+        self.ponderhit.store(true, Ordering::Relaxed);
+        self.handle.thread().unpark();
+    }
+
+    /// A panicking search is reported, and the GUI still gets the bestmove it waits for
+    fn wait(self, output: &Mutex<impl io::Write>) -> Result<()> {
+        if self.handle.join().is_err() {
+            output.clear_poison();
+            let mut output = output.lock().unwrap();
+            writeln!(output, "info string Search thread panicked")?;
+            writeln!(output, "bestmove 0000")?;
+        }
+        Ok(())
+    }
 }
 
 impl UciState {
     pub fn new() -> Self {
         UciState {
             debug: true,
-            board: None,
+            game: None,
+            tt: Arc::new(TranspositionTable::new(DEFAULT_TT_SIZE_MB)),
+            move_overhead: DEFAULT_MOVE_OVERHEAD,
+            threads: 1,
+            multi_pv: 1,
+            features: SearchFeatures::default(),
         }
     }
 
+    /// Handle UCI commands until quit or end of input. Searches run on a separate thread, so
+    /// that stop and isready are answered while searching. At end of input, a running search
+    /// is completed before returning.
     pub fn run_uci_input(
         &mut self,
         input: &mut impl io::Read,
-        output: &mut impl io::Write,
+        output: &mut (impl io::Write + Send),
     ) -> Result<()> {
-        let buf_read = BufReader::new(input);
-        for read_line in buf_read.lines() {
-            let line = read_line?;
-            let message = vampirc_uci::parse_one(&line);
-            eprintln!("Received message: {}", line);
-
-            match message {
-                UciMessage::Uci => {
-                    writeln!(output, "id name fisk")?;
-                    writeln!(output, "id author Aksel Slettemark")?;
-                    writeln!(output, "uciok")?;
+        let output = Mutex::new(output);
+        thread::scope(|scope| {
+            let mut search: Option<SearchThread> = None;
+
+            let buf_read = BufReader::new(input);
+            for read_line in buf_read.lines() {
+                let line = read_line?;
+                let message = vampirc_uci::parse_one(&line);
+                if self.debug {
+                    eprintln!("Received message: {}", line);
                 }
-                UciMessage::Debug(dbg) => self.debug = dbg,
-                UciMessage::IsReady => writeln!(output, "readyok")?,
-                UciMessage::Register { .. } => todo!(),
-                UciMessage::Position {
-                    startpos,
-                    fen,
-                    moves,
-                } => self.position(startpos, fen, moves),
-                UciMessage::SetOption { name: _, value: _ } => todo!(),
-                UciMessage::UciNewGame => self.board = None,
-                UciMessage::Stop => todo!(),
-                UciMessage::PonderHit => todo!(),
-                UciMessage::Quit => {
-                    return Ok(());
+
+                let result = match message {
+                    UciMessage::Uci => {
+                        let mut output = output.lock().unwrap();
+                        writeln!(output, "id name fisk")?;
+                        writeln!(output, "id author Aksel Slettemark")?;
+                        for option in OPTIONS.iter() {
+                            writeln!(output, "{}", option.declaration())?;
+                        }
+                        writeln!(output, "uciok")?;
+                        Ok(())
+                    }
+                    UciMessage::Debug(dbg) => {
+                        self.debug = dbg;
+                        Ok(())
+                    }
+                    UciMessage::IsReady => {
+                        writeln!(output.lock().unwrap(), "readyok")?;
+                        Ok(())
+                    }
+                    UciMessage::Stop => {
+                        if let Some(search) = search.take() {
+                            search.stop(&output)?;
+                        }
+                        Ok(())
+                    }
+                    UciMessage::PonderHit => {
+                        if let Some(search) = &search {
+                            search.ponderhit();
+                        }
+                        Ok(())
+                    }
+                    UciMessage::Quit => {
+                        if let Some(search) = search.take() {
+                            search.stop(&output)?;
+                        }
+                        return Ok(());
+                    }
+                    UciMessage::Unknown(_, _) if line.trim().is_empty() => Ok(()),
+                    UciMessage::Unknown(text, _) => Err(UciError::UnknownCommand(text)),
+                    message => {
+                        // The remaining commands change the state the search is using
+                        if let Some(search) = search.take() {
+                            search.stop(&output)?;
+                        }
+
+                        match message {
+                            UciMessage::Register { .. } => Err(UciError::Unsupported("register")),
+                            UciMessage::Position {
+                                startpos,
+                                fen,
+                                moves,
+                            } => self.position(startpos, fen, moves),
+                            UciMessage::SetOption { name, value } => {
+                                self.set_option(&name, value.as_deref())
+                            }
+                            UciMessage::UciNewGame => {
+                                self.game = None;
+                                self.tt_mut().map(TranspositionTable::clear)
+                            }
+                            UciMessage::Go {
+                                time_control,
+                                search_control,
+                            } => match self.go(
+                                scope,
+                                time_control,
+                                search_control,
+                                // The parser drops ponder when a clock is also given
+                                line.split_whitespace().any(|word| word == "ponder"),
+                                &output,
+                            ) {
+                                Ok(started) => {
+                                    search = Some(started);
+                                    Ok(())
+                                }
+                                Err(e) => {
+                                    // The GUI waits for a bestmove after go
+                                    let mut output = output.lock().unwrap();
+                                    writeln!(output, "info string {}", e)?;
+                                    writeln!(output, "bestmove 0000")?;
+                                    Ok(())
+                                }
+                            },
+                            _ => Err(UciError::UnexpectedMessage(line)),
+                        }
+                    }
+                };
+
+                if let Err(e) = result {
+                    writeln!(output.lock().unwrap(), "info string {}", e)?;
                 }
-                UciMessage::Go {
-                    time_control,
-                    search_control,
-                } => self.go(time_control, search_control, output),
-                UciMessage::Unknown(_, _) => todo!(),
-                _ => {}
             }
-        }
 
-        Ok(())
+            if let Some(search) = search {
+                if search.waits_for_stop() {
+                    search.stop(&output)?;
+                } else {
+                    search.wait(&output)?;
+                }
+            }
+            Ok(())
+        })
+    }
+
+    /// The transposition table is only changed between searches, when no search thread holds a
+    /// reference to it
+    fn tt_mut(&mut self) -> std::result::Result<&mut TranspositionTable, UciError> {
+        Arc::get_mut(&mut self.tt).ok_or(UciError::TableInUse)
     }
 
-    fn position(&mut self, startpos: bool, fen: Option<UciFen>, moves: Vec<UciMove>) {
+    /// Set up the position of a position command. On errors the position is cleared, so that a
+    /// later go doesn't search a stale position
+    pub fn position(
+        &mut self,
+        startpos: bool,
+        fen: Option<UciFen>,
+        moves: Vec<UciMove>,
+    ) -> std::result::Result<(), UciError> {
+        self.game = None;
         let fen_string = match (startpos, &fen) {
             (true, _) => fen::FEN_DEFAULT_BOARD,
             (false, Some(uci_fen)) => uci_fen.0.as_str(),
-            (false, None) => panic!("Invalid position command"),
+            (false, None) => return Err(UciError::MissingPosition),
         };
 
-        let mut board = Board::from_fen(fen_string).expect("Invalid fen string");
+        let board = Board::from_fen(fen_string)
+            .map_err(|e| UciError::InvalidFen(fen_string.to_string(), e))?;
         if self.debug {
             eprint!("Initial board");
             eprintln!("{}", board);
         }
+        let mut game = Game::new(board);
         for uci_move in moves {
-            let fisk_move = uci_move_to_fisk_move(uci_move, &board).expect("Invalid UciMove");
-            board.make_move_in_place(&fisk_move);
+            let fisk_move = game
+                .board()
+                .parse_uci_move(&uci_move.to_string())
+                .map_err(|e| UciError::IllegalMove(e.0))?;
+            game.make_move(&fisk_move);
             if self.debug {
                 eprintln!("After {}", uci_move);
-                eprintln!("{}", board);
+                eprintln!("{}", game.board());
             }
         }
 
-        self.board = Some(board);
+        self.game = Some(game);
+        Ok(())
+    }
+
+    fn set_option(&mut self, name: &str, value: Option<&str>) -> std::result::Result<(), UciError> {
+        let option = find_option(name).ok_or_else(|| UciError::UnknownOption(name.to_string()))?;
+        let value = option
+            .parse_value(value)
+            .map_err(UciError::InvalidOptionValue)?;
+        match (option.name, value) {
+            (HASH, OptionValue::Spin(mb)) => self.tt_mut()?.resize(mb as usize),
+            (THREADS, OptionValue::Spin(threads)) => self.threads = threads as usize,
+            (MULTI_PV, OptionValue::Spin(multi_pv)) => self.multi_pv = multi_pv as usize,
+            (MOVE_OVERHEAD, OptionValue::Spin(ms)) => {
+                self.move_overhead = Duration::from_millis(ms as u64)
+            }
+            (DEBUG, OptionValue::Check(debug)) => self.debug = debug,
+            (QUIET_MOVE_ORDERING, OptionValue::Check(on)) => self.features.quiet_move_ordering = on,
+            (NULL_MOVE, OptionValue::Check(on)) => self.features.null_move = on,
+            (NULL_MOVE_VERIFICATION, OptionValue::Check(on)) => {
+                self.features.null_move_verification = on
+            }
+            (LATE_MOVE_REDUCTIONS, OptionValue::Check(on)) => {
+                self.features.late_move_reductions = on
+            }
+            (FUTILITY_PRUNING, OptionValue::Check(on)) => self.features.futility_pruning = on,
+            (REVERSE_FUTILITY_PRUNING, OptionValue::Check(on)) => {
+                self.features.reverse_futility_pruning = on
+            }
+            (PVS, OptionValue::Check(on)) => self.features.pvs = on,
+            _ => unreachable!(),
+        }
+        Ok(())
     }
 
-    fn go(
+    /// A ponder search searches without limits until ponderhit, and then uses the time control
+    /// sent along with go ponder
+    fn go<'scope>(
         &mut self,
+        scope: &'scope Scope<'scope, '_>,
         time_control: Option<UciTimeControl>,
         search_control: Option<UciSearchControl>,
-        output: &mut impl io::Write,
-    ) {
-        let board = if let Some(board) = self.board {
-            board
-        } else {
-            eprint!("Go with no position");
-            return;
-        };
+        ponder: bool,
+        output: &'scope Mutex<impl io::Write + Send>,
+    ) -> std::result::Result<SearchThread<'scope>, UciError> {
+        let game = self.game.as_ref().ok_or(UciError::NoPosition)?;
+        let board = *game.board();
+        let history = game.keys().to_vec();
 
-        // TODO if-let-chain
-        let mut depth = if let Some(control) = search_control {
-            if let Some(depth) = control.depth {
-                depth
-            } else {
-                6
-            }
-        } else {
-            6
+        let time_limits = time_control
+            .as_ref()
+            .and_then(|tc| TimeLimits::from_uci(tc, board.white_to_move(), self.move_overhead));
+        let depth = match search_control.and_then(|control| control.depth) {
+            Some(depth) => depth as usize,
+            None if time_limits.is_some() => MAX_DEPTH,
+            None => DEFAULT_GO_DEPTH,
         };
+        let infinite = matches!(time_control, Some(UciTimeControl::Infinite));
 
-        if let Some(UciTimeControl::TimeLeft {
-            white_time,
-            black_time,
-            white_increment: _,
-            black_increment: _,
-            moves_to_go: _,
-        }) = time_control
-        {
-            if let Some(board) = self.board {
-                let white_to_move = board.white_to_move();
-                if white_to_move && let Some(wtime) = white_time && wtime.num_seconds() > 120 {
-                    depth = 8;
-                } else if !white_to_move && let Some(btime) = black_time && btime.num_seconds() > 120 {
-                    depth = 8;
+        let time_limits = time_limits.unwrap_or_else(TimeLimits::unlimited);
+        let (threads, multi_pv, features) = (self.threads, self.multi_pv, self.features);
+        self.tt_mut()?.new_search();
+        let tt = self.tt.clone();
+        let stop = Arc::new(AtomicBool::new(false));
+        let search_stop = stop.clone();
+        // Searches that don't ponder start out as if the ponder hit already happened
+        let ponderhit = Arc::new(AtomicBool::new(!ponder));
+        let search_ponderhit = ponderhit.clone();
+        let handle = scope.spawn(move || {
+            let result = thread::scope(|helpers| {
+                // Helper threads fill the shared transposition table, and are stopped when the
+                // main search finishes https://www.chessprogramming.org/Lazy_SMP
+                let helper_stop = Arc::new(AtomicBool::new(false));
+                for _ in 1..threads {
+                    let (tt, helper_stop, history) = (&tt, helper_stop.clone(), &history);
+                    let ponderhit = search_ponderhit.clone();
+                    helpers.spawn(move || {
+                        Search::new(tt)
+                            .with_features(features)
+                            .with_stop_flag(helper_stop)
+                            .with_history(history)
+                            .with_time_limits(time_limits)
+                            .with_ponderhit_flag(ponderhit)
+                            .iterative_deepening(&board, depth)
+                    });
                 }
+
+                let result = Search::new(&tt)
+                    .with_features(features)
+                    .with_stop_flag(search_stop.clone())
+                    .with_time_limits(time_limits)
+                    .with_ponderhit_flag(search_ponderhit.clone())
+                    .with_multi_pv(multi_pv)
+                    .with_history(&history)
+                    .with_info_callback(|info| {
+                        writeln!(output.lock().unwrap(), "{}", info_text(info)).unwrap()
+                    })
+                    .iterative_deepening(&board, depth);
+                helper_stop.store(true, Ordering::Relaxed);
+                result
+            });
+
+            // bestmove must not be sent before stop, or ponderhit for ponder searches
+            while (infinite || !search_ponderhit.load(Ordering::Relaxed))
+                && !search_stop.load(Ordering::Relaxed)
+            {
+                thread::park();
             }
-        }
 
-        let (_eval, best_move) = board.best_move(depth as usize);
-        writeln!(
-            output,
-            "bestmove {}",
-            fisk_move_to_uci_text(&best_move.unwrap())
-        )
-        .unwrap();
+            // No legal moves, the game is over
+            let best_move = result
+                .best_move()
+                .map(|m| m.to_uci())
+                .unwrap_or_else(|| "0000".to_string());
+            writeln!(output.lock().unwrap(), "bestmove {}", best_move).unwrap();
+        });
+
+        Ok(SearchThread {
+            handle,
+            stop,
+            ponderhit,
+            infinite,
+        })
     }
 }
 
@@ -148,88 +423,29 @@ impl Default for UciState {
     }
 }
 
-fn uci_move_to_fisk_move(uci_move: UciMove, board: &Board) -> Option<Move> {
-    let from = uci_square_to_tzcnt_pos(&uci_move.from)?;
-    let to = uci_square_to_tzcnt_pos(&uci_move.to)?;
-    let from_pos = 1u64 << from;
-    let to_pos = 1u64 << to;
-    let coverage = board.bitboard.coverage();
-
-    let capture = intersects(coverage, to_pos);
-    if let Some(promotion) = uci_move.promotion {
-        return match promotion {
-            vampirc_uci::UciPiece::Knight => Some(Move::new(from, to, capture, 0b1000)),
-            vampirc_uci::UciPiece::Bishop => Some(Move::new(from, to, capture, 0b1001)),
-            vampirc_uci::UciPiece::Rook => Some(Move::new(from, to, capture, 0b1010)),
-            vampirc_uci::UciPiece::Queen => Some(Move::new(from, to, capture, 0b1011)),
-            _ => None,
-        };
+fn info_text(info: &SearchInfo) -> String {
+    let mut text = format!(
+        "info depth {} seldepth {} multipv {}",
+        info.depth, info.seldepth, info.multipv
+    );
+    match info.score.map(|score| (score, mate_in_moves(score))) {
+        Some((_, Some(mate))) => text += &format!(" score mate {}", mate),
+        Some((score, None)) => text += &format!(" score cp {}", score),
+        None => {}
     }
-
-    let from_piece = board.slow_kind_at(from_pos);
-    let bitboard_square_index_abs_diff = (from as i32 - to as i32).unsigned_abs();
-
-    if from_piece.is_pawn() {
-        if bitboard_square_index_abs_diff == 16 {
-            // Double pawn push
-            return Some(Move::new(from, to, false, 0b1));
-        }
-        if !intersects(coverage, to_pos)
-            && (bitboard_square_index_abs_diff == 7 || bitboard_square_index_abs_diff == 9)
-        {
-            // EP capture
-            return Some(Move::new(from, to, true, 0b1));
+    text += &format!(
+        " nodes {} nps {} time {} hashfull {}",
+        info.nodes,
+        info.nps(),
+        info.time.as_millis(),
+        info.hashfull
+    );
+    if !info.pv.is_empty() {
+        text += " pv";
+        for m in info.pv {
+            text += " ";
+            text += &m.to_uci();
         }
     }
-
-    if from_piece == PieceKind::WhiteKing && from == 4 {
-        if to == 6 {
-            // Kingside castle
-            return Some(Move::new(from, to, false, 0b10));
-        }
-        if to == 2 {
-            // Queenside castle
-            return Some(Move::new(from, to, false, 0b11));
-        }
-    }
-
-    if from_piece == PieceKind::BlackKing && from == 60 {
-        if to == 62 {
-            // Kingside castle
-            return Some(Move::new(from, to, false, 0b10));
-        }
-        if to == 58 {
-            // Queenside castle
-            return Some(Move::new(from, to, false, 0b11));
-        }
-    }
-
-    // Quiet move or normal capture
-    Some(Move::new(from, to, capture, 0))
-}
-
-fn uci_square_to_tzcnt_pos(square: &UciSquare) -> Option<u8> {
-    let square_name = format!("{}{}", square.file, square.rank);
-    constants::SQUARE_NAME
-        .iter()
-        .position(|x| x == &square_name)
-        .map(|x| x as u8)
-}
-
-fn fisk_move_to_uci_text(mov: &Move) -> String {
-    let from = SQUARE_NAME[mov.from() as usize];
-    let to = SQUARE_NAME[mov.to() as usize];
-
-    if mov.is_promotion() {
-        let char = match mov.flags_nibble() & 0b11 {
-            0b00 => "k",
-            0b01 => "b",
-            0b10 => "r",
-            0b11 => "q",
-            _ => unreachable!(),
-        };
-        return format!("{}{}{}", from, to, char);
-    }
-
-    format!("{}{}", from, to)
+    text
 }
@@ -0,0 +1,152 @@
+use vampirc_uci::{Serializable, UciOptionConfig};
+
+use crate::time_management::DEFAULT_MOVE_OVERHEAD;
+use crate::transposition::DEFAULT_TT_SIZE_MB;
+
+pub const HASH: &str = "Hash";
+pub const THREADS: &str = "Threads";
+pub const MULTI_PV: &str = "MultiPV";
+pub const MOVE_OVERHEAD: &str = "Move Overhead";
+pub const DEBUG: &str = "Debug";
+/// Switches for the search features, to measure their effect in engine matches
+pub const QUIET_MOVE_ORDERING: &str = "Quiet Move Ordering";
+pub const NULL_MOVE: &str = "Null Move Pruning";
+pub const NULL_MOVE_VERIFICATION: &str = "Null Move Verification";
+pub const LATE_MOVE_REDUCTIONS: &str = "Late Move Reductions";
+pub const FUTILITY_PRUNING: &str = "Futility Pruning";
+pub const REVERSE_FUTILITY_PRUNING: &str = "Reverse Futility Pruning";
+pub const PVS: &str = "Principal Variation Search";
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum OptionType {
+    Spin { default: i64, min: i64, max: i64 },
+    Check { default: bool },
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum OptionValue {
+    Spin(i64),
+    Check(bool),
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct UciOption {
+    pub name: &'static str,
+    pub option_type: OptionType,
+}
+
+/// Options advertised in the uci handshake and accepted by setoption
+pub const OPTIONS: [UciOption; 12] = [
+    UciOption {
+        name: HASH,
+        option_type: OptionType::Spin {
+            default: DEFAULT_TT_SIZE_MB as i64,
+            min: 1,
+            max: 32768,
+        },
+    },
+    UciOption {
+        name: THREADS,
+        option_type: OptionType::Spin {
+            default: 1,
+            min: 1,
+            max: 256,
+        },
+    },
+    UciOption {
+        name: MULTI_PV,
+        option_type: OptionType::Spin {
+            default: 1,
+            min: 1,
+            max: 256,
+        },
+    },
+    UciOption {
+        name: MOVE_OVERHEAD,
+        option_type: OptionType::Spin {
+            default: DEFAULT_MOVE_OVERHEAD.as_millis() as i64,
+            min: 0,
+            max: 5000,
+        },
+    },
+    UciOption {
+        name: DEBUG,
+        option_type: OptionType::Check { default: true },
+    },
+    UciOption {
+        name: QUIET_MOVE_ORDERING,
+        option_type: OptionType::Check { default: true },
+    },
+    UciOption {
+        name: NULL_MOVE,
+        option_type: OptionType::Check { default: true },
+    },
+    UciOption {
+        name: NULL_MOVE_VERIFICATION,
+        option_type: OptionType::Check { default: true },
+    },
+    UciOption {
+        name: LATE_MOVE_REDUCTIONS,
+        option_type: OptionType::Check { default: true },
+    },
+    UciOption {
+        name: FUTILITY_PRUNING,
+        option_type: OptionType::Check { default: true },
+    },
+    UciOption {
+        name: REVERSE_FUTILITY_PRUNING,
+        option_type: OptionType::Check { default: true },
+    },
+    UciOption {
+        name: PVS,
+        option_type: OptionType::Check { default: true },
+    },
+];
+
+/// Option names are case insensitive
+pub fn find_option(name: &str) -> Option<&'static UciOption> {
+    OPTIONS
+        .iter()
+        .find(|option| option.name.eq_ignore_ascii_case(name.trim()))
+}
+
+impl UciOption {
+    /// The option line sent in reply to uci
+    pub fn declaration(&self) -> String {
+        let name = self.name.to_string();
+        let config = match self.option_type {
+            OptionType::Spin { default, min, max } => UciOptionConfig::Spin {
+                name,
+                default: Some(default),
+                min: Some(min),
+                max: Some(max),
+            },
+            OptionType::Check { default } => UciOptionConfig::Check {
+                name,
+                default: Some(default),
+            },
+        };
+        config.serialize()
+    }
+
+    pub fn parse_value(&self, value: Option<&str>) -> Result<OptionValue, String> {
+        let value = value.map(str::trim).unwrap_or("");
+        match self.option_type {
+            OptionType::Spin { min, max, .. } => match value.parse::<i64>() {
+                Ok(v) if (min..=max).contains(&v) => Ok(OptionValue::Spin(v)),
+                _ => Err(format!(
+                    "Invalid value '{}' for option {}, expected an integer from {} to {}",
+                    value, self.name, min, max
+                )),
+            },
+            OptionType::Check { .. } => match value {
+                "true" => Ok(OptionValue::Check(true)),
+                "false" => Ok(OptionValue::Check(false)),
+                _ => Err(format!(
+                    "Invalid value '{}' for option {}, expected true or false",
+                    value, self.name
+                )),
+            },
+        }
+    }
+}
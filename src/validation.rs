@@ -0,0 +1,272 @@
+use std::fmt;
+
+use bitintr::*;
+
+use crate::board::Board;
+use crate::constants::*;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PositionViolation {
+    /// Each side has exactly one king
+    KingCount {
+        white: bool,
+        count: u32,
+    },
+    /// Pawns can't stand on the first or last row
+    PawnOnBackRank {
+        square: u8,
+    },
+    TooManyPawns {
+        white: bool,
+        count: u32,
+    },
+    /// More pieces than the starting pieces plus promotions of the missing pawns
+    ImpossiblePieceCount {
+        white: bool,
+    },
+    /// The side that just moved left its king in check
+    OpponentInCheck,
+    KingsAdjacent,
+    /// A castling right, as written in FEN, without the king and rook on their starting squares
+    CastlingRight {
+        right: char,
+    },
+    /// No pawn that just moved two squares past the en passant square
+    EnPassant {
+        square: u8,
+    },
+}
+
+impl PositionViolation {
+    /// Violations that move generation and search can't handle, as opposed to positions that
+    /// merely can't be reached in a game. With the side not to move in check, the king could be
+    /// captured.
+    pub fn is_unplayable(&self) -> bool {
+        matches!(
+            self,
+            PositionViolation::KingCount { .. }
+                | PositionViolation::PawnOnBackRank { .. }
+                | PositionViolation::TooManyPawns { .. }
+                | PositionViolation::OpponentInCheck
+                | PositionViolation::KingsAdjacent
+                | PositionViolation::CastlingRight { .. }
+                | PositionViolation::EnPassant { .. }
+        )
+    }
+}
+
+fn color_name(white: bool) -> &'static str {
+    if white {
+        "White"
+    } else {
+        "Black"
+    }
+}
+
+impl fmt::Display for PositionViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PositionViolation::KingCount { white, count } => {
+                write!(f, "{} has {} kings", color_name(*white), count)
+            }
+            PositionViolation::PawnOnBackRank { square } => {
+                write!(f, "Pawn on back rank {}", SQUARE_NAME[*square as usize])
+            }
+            PositionViolation::TooManyPawns { white, count } => {
+                write!(f, "{} has {} pawns", color_name(*white), count)
+            }
+            PositionViolation::ImpossiblePieceCount { white } => write!(
+                f,
+                "{} has more promoted pieces than missing pawns",
+                color_name(*white)
+            ),
+            PositionViolation::OpponentInCheck => write!(f, "The side not to move is in check"),
+            PositionViolation::KingsAdjacent => write!(f, "The kings are adjacent"),
+            PositionViolation::CastlingRight { right } => write!(
+                f,
+                "Castling right {} without king and rook on their starting squares",
+                right
+            ),
+            PositionViolation::EnPassant { square } => write!(
+                f,
+                "En passant square {} without a pawn that just moved two squares",
+                SQUARE_NAME[*square as usize]
+            ),
+        }
+    }
+}
+
+impl Board {
+    /// Every way the position could not have been reached in a game, empty if none are found
+    pub fn validate(&self) -> Vec<PositionViolation> {
+        let mut violations = Vec::new();
+        let bb = &self.bitboard;
+
+        let white_kings = bb.white_king.popcnt() as u32;
+        let black_kings = bb.black_king.popcnt() as u32;
+        if white_kings != 1 {
+            violations.push(PositionViolation::KingCount {
+                white: true,
+                count: white_kings,
+            });
+        }
+        if black_kings != 1 {
+            violations.push(PositionViolation::KingCount {
+                white: false,
+                count: black_kings,
+            });
+        }
+
+        let mut back_rank_pawns = (bb.white_pawns | bb.black_pawns) & (ROW_1 | ROW_8);
+        while back_rank_pawns != 0 {
+            let square = back_rank_pawns.tzcnt() as u8;
+            violations.push(PositionViolation::PawnOnBackRank { square });
+            back_rank_pawns &= back_rank_pawns - 1;
+        }
+
+        self.validate_piece_counts(true, &mut violations);
+        self.validate_piece_counts(false, &mut violations);
+
+        // Checks and adjacency are only defined with one king each
+        if white_kings == 1 && black_kings == 1 {
+            if self.is_in_check(!self.white_to_move()) {
+                violations.push(PositionViolation::OpponentInCheck);
+            }
+            if intersects(
+                KING_ATTACK_MASK[bb.white_king.tzcnt() as usize],
+                bb.black_king,
+            ) {
+                violations.push(PositionViolation::KingsAdjacent);
+            }
+        }
+
+        self.validate_castling(&mut violations);
+        self.validate_en_passant(&mut violations);
+
+        violations
+    }
+
+    /// Strict validation, see validate
+    pub fn is_sane_position(&self) -> bool {
+        self.validate().is_empty()
+    }
+
+    fn validate_piece_counts(&self, white: bool, violations: &mut Vec<PositionViolation>) {
+        let bb = &self.bitboard;
+        let (pawns, rooklike, bishoplike, knights) = if white {
+            (
+                bb.white_pawns,
+                bb.white_rooklike,
+                bb.white_bishoplike,
+                bb.white_knights,
+            )
+        } else {
+            (
+                bb.black_pawns,
+                bb.black_rooklike,
+                bb.black_bishoplike,
+                bb.black_knights,
+            )
+        };
+
+        let pawn_count = pawns.popcnt() as u32;
+        if pawn_count > 8 {
+            violations.push(PositionViolation::TooManyPawns {
+                white,
+                count: pawn_count,
+            });
+            return;
+        }
+
+        let queens = (rooklike & bishoplike).popcnt() as u32;
+        let rooks = (rooklike & !bishoplike).popcnt() as u32;
+        let bishops = (bishoplike & !rooklike).popcnt() as u32;
+        let knights = knights.popcnt() as u32;
+        let promoted = queens.saturating_sub(1)
+            + rooks.saturating_sub(2)
+            + bishops.saturating_sub(2)
+            + knights.saturating_sub(2);
+        if pawn_count + promoted > 8 {
+            violations.push(PositionViolation::ImpossiblePieceCount { white });
+        }
+    }
+
+    fn validate_castling(&self, violations: &mut Vec<PositionViolation>) {
+        let bb = &self.bitboard;
+        let white_rooks = bb.white_rooklike & !bb.white_bishoplike;
+        let black_rooks = bb.black_rooklike & !bb.black_bishoplike;
+
+        let rights = [
+            (
+                self.can_white_castle_kingside(),
+                'K',
+                bb.white_king,
+                white_rooks,
+                4,
+                7,
+            ),
+            (
+                self.can_white_castle_queenside(),
+                'Q',
+                bb.white_king,
+                white_rooks,
+                4,
+                0,
+            ),
+            (
+                self.can_black_castle_kingside(),
+                'k',
+                bb.black_king,
+                black_rooks,
+                60,
+                63,
+            ),
+            (
+                self.can_black_castle_queenside(),
+                'q',
+                bb.black_king,
+                black_rooks,
+                60,
+                56,
+            ),
+        ];
+        for (available, right, king, rooks, king_square, rook_square) in rights.iter() {
+            if *available
+                && !(intersects(*king, 1 << king_square) && intersects(*rooks, 1 << rook_square))
+            {
+                violations.push(PositionViolation::CastlingRight { right: *right });
+            }
+        }
+    }
+
+    fn validate_en_passant(&self, violations: &mut Vec<PositionViolation>) {
+        let file = self.get_en_passant_file();
+        if file == 0 {
+            return;
+        }
+        let file_index = file as usize - 1;
+
+        // The pawn moved from start to pawn, passing the en passant square
+        let (square, pawn, start, pawns) = if self.white_to_move() {
+            (
+                40 + file_index,
+                32 + file_index,
+                48 + file_index,
+                self.bitboard.black_pawns,
+            )
+        } else {
+            (
+                16 + file_index,
+                24 + file_index,
+                8 + file_index,
+                self.bitboard.white_pawns,
+            )
+        };
+        let coverage = self.bitboard.coverage();
+        if !intersects(pawns, 1 << pawn) || intersects(coverage, (1 << square) | (1 << start)) {
+            violations.push(PositionViolation::EnPassant {
+                square: square as u8,
+            });
+        }
+    }
+}
@@ -0,0 +1,90 @@
+use crate::board::Board;
+use crate::constants::TZCNT_U64_ZEROS;
+
+/// Zobrist hashing keys https://www.chessprogramming.org/Zobrist_Hashing
+/// Piece keys are indexed by the PieceKind discriminant and the tzcnt position of the piece
+pub struct ZobristKeys {
+    pub pieces: [[u64; 64]; 16],
+    pub black_to_move: u64,
+    /// Indexed by castling flag bits 1-4 shifted down, see Board flags
+    pub castling: [u64; 16],
+    /// Indexed by en passant file as stored in Board flags, 0 meaning no en passant
+    pub en_passant_file: [u64; 9],
+}
+
+lazy_static! {
+    pub static ref ZOBRIST: ZobristKeys = generate_zobrist_keys();
+}
+
+/// Fixed seed so that keys (and thereby searches) are reproducible between runs
+const ZOBRIST_SEED: u64 = 0x5EED_F15C_0DDB_A115;
+
+fn generate_zobrist_keys() -> ZobristKeys {
+    let mut state = ZOBRIST_SEED;
+    let mut next = || xorshift64star(&mut state);
+
+    let mut keys = ZobristKeys {
+        pieces: [[0; 64]; 16],
+        black_to_move: 0,
+        castling: [0; 16],
+        en_passant_file: [0; 9],
+    };
+
+    for kind in keys.pieces.iter_mut() {
+        for square in kind.iter_mut() {
+            *square = next();
+        }
+    }
+    keys.black_to_move = next();
+    for castling in keys.castling.iter_mut() {
+        *castling = next();
+    }
+    // Index 0 is "no en passant" and must not alter the key
+    for file in keys.en_passant_file.iter_mut().skip(1) {
+        *file = next();
+    }
+
+    keys
+}
+
+// https://www.chessprogramming.org/Pseudo-Random_Number_Generator
+fn xorshift64star(state: &mut u64) -> u64 {
+    let mut x = *state;
+    x ^= x >> 12;
+    x ^= x << 25;
+    x ^= x >> 27;
+    *state = x;
+    x.wrapping_mul(0x2545_F491_4F6C_DD1D)
+}
+
+/// Key of the non-piece state: side to move, castling availability and en passant file
+#[inline]
+pub fn state_key(white_to_move: bool, castling_bits: u8, en_passant_file: u8) -> u64 {
+    let mut key = ZOBRIST.castling[castling_bits as usize & 0xF]
+        ^ ZOBRIST.en_passant_file[en_passant_file as usize];
+    if !white_to_move {
+        key ^= ZOBRIST.black_to_move;
+    }
+    key
+}
+
+impl Board {
+    /// Compute the Zobrist key from scratch. Board keeps its key updated incrementally,
+    /// so this is mainly useful for initialization and verification.
+    pub fn compute_zobrist(&self) -> u64 {
+        let mut key = state_key(
+            self.white_to_move(),
+            self.castling_bits(),
+            self.get_en_passant_file(),
+        );
+
+        for (i, pos_tzcnt) in self.piece_positions_tzcnt.iter().enumerate() {
+            if *pos_tzcnt == TZCNT_U64_ZEROS {
+                continue;
+            }
+            key ^= ZOBRIST.pieces[self.piece_kinds[i] as usize][*pos_tzcnt as usize];
+        }
+
+        key
+    }
+}
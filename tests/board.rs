@@ -15,7 +15,7 @@ fn test_check(fenstr: &str, white: bool, black: bool) {
 #[test]
 fn memsizes() {
     assert_eq!(size_of::<PieceKind>(), 1); // Not using more memory than u8
-    assert_eq!(size_of::<Board>(), 152); // We don't want to accidentally change the Board size
+    assert_eq!(size_of::<Board>(), 296); // We don't want to accidentally change the Board size
 }
 
 #[test]
@@ -34,12 +34,12 @@ fn knight_check() {
     test_check("8/5k2/8/8/3n4/3n4/1K6/8 w - - 0 1", true, false);
     test_check("8/5k2/8/8/2n5/3n4/1K6/8 w - - 0 1", true, false);
     test_check("8/5k2/8/8/1n6/8/7K/8 w - - 0 1", false, false);
-    test_check("8/5k2/8/6N1/1n6/8/7K/8 w - - 0 1", false, true);
+    test_check("8/5k2/8/6N1/1n6/8/7K/8 b - - 0 1", false, true);
 }
 
 #[test]
 fn bishoplike_check() {
-    test_check("8/5k2/8/8/2B5/1n6/7K/8 w - - 0 1", false, true);
+    test_check("8/5k2/8/8/2B5/1n6/7K/8 b - - 0 1", false, true);
     test_check("8/5k2/8/3n4/2B5/8/7K/8 w - - 0 1", false, false);
     test_check("8/5k2/2b5/3n4/2B5/8/6K1/8 w - - 0 1", false, false);
 }
@@ -67,5 +67,5 @@ fn pawn_check() {
         false,
     );
     test_check("8/8/k7/7P/8/p7/1K6/8 w - - 0 1", true, false);
-    test_check("8/8/6k1/7P/8/p7/1K6/8 w - - 0 1", true, true);
+    test_check("8/8/6k1/7P/8/8/1K6/8 b - - 0 1", false, true);
 }
@@ -5,3 +5,13 @@ fn default_board_has_symmetric_eval() {
     let e = Board::default().eval();
     assert_eq!(e, 0);
 }
+
+#[test]
+fn relative_eval_is_from_side_to_move() {
+    let white = Board::from_fen("4k3/8/8/8/8/8/8/3QK3 w - - 0 1").unwrap();
+    let black = Board::from_fen("4k3/8/8/8/8/8/8/3QK3 b - - 0 1").unwrap();
+    assert!(white.eval() > 0);
+    assert_eq!(black.eval(), white.eval());
+    assert_eq!(white.relative_eval(), white.eval());
+    assert_eq!(black.relative_eval(), -black.eval());
+}
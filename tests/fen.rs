@@ -1,7 +1,10 @@
 use fisk::board::Board;
-use fisk::board::PieceKind::EmptySquare;
+use fisk::board::PieceKind::*;
 use fisk::constants::*;
-use fisk::fen::FEN_DEFAULT_BOARD;
+use fisk::fen::{FenError, FenField, FEN_DEFAULT_BOARD};
+use fisk::move_representation::Move;
+use fisk::perft::perft_fens;
+use fisk::validation::PositionViolation;
 
 #[test]
 fn default_board_fen() {
@@ -19,7 +22,8 @@ fn default_board_fen() {
         "Row 7 is filled with black pawns"
     );
 
-    for kind in a.piece_kinds.iter() {
+    assert_eq!(a.piece_list_len(), 32);
+    for kind in a.piece_kinds[..a.piece_list_len()].iter() {
         assert_ne!(*kind, EmptySquare, "Piece list is filled");
     }
     assert!(a.white_to_move());
@@ -46,3 +50,162 @@ fn en_passant() {
         Board::from_fen("rnbqkbnr/1ppp1ppp/p7/3Pp3/8/8/PPP1PPPP/RNBQKBNR w KQkq e6 0 1").unwrap();
     assert_eq!(b.get_en_passant_file(), 5);
 }
+
+#[test]
+fn more_than_32_pieces() {
+    for fen in [
+        // Extra promoted pieces
+        "rnbqkbnr/pppppppp/nnnnnnnn/8/8/NNNNNNNN/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "qqqqkqqq/pppppppp/bbbbbbbb/8/8/QQQQQQQQ/PPPPPPPP/RNBQKBNR b KQ - 0 1",
+        // Every square occupied
+        "rrrrkrrr/pppppppp/bbbbbbbb/nnnnnnnn/NNNNNNNN/BBBBBBBB/PPPPPPPP/RRRRKRRR w - - 0 1",
+    ] {
+        let board = Board::from_fen(fen).unwrap();
+        assert_eq!(board.to_fen(), fen);
+        assert_eq!(
+            board.piece_list_len() as u32,
+            board.bitboard.coverage().count_ones()
+        );
+
+        let mut after_moves = board;
+        for m in board.generate_legal_moves() {
+            let undo = after_moves.make_move_in_place(&m);
+            assert_eq!(after_moves, board.make_move(&m));
+            after_moves.unmake_move(&m, &undo);
+        }
+        assert_eq!(after_moves, board);
+    }
+}
+
+#[test]
+fn reject_overlong_row() {
+    assert!(Board::from_fen("rnbqkbnrr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").is_err());
+}
+
+#[test]
+fn to_fen() {
+    assert_eq!(Board::default().to_fen(), FEN_DEFAULT_BOARD);
+    for fen in [
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "rnbqkbnr/1ppp1ppp/p7/3Pp3/8/8/PPP1PPPP/RNBQKBNR w KQkq e6 0 1",
+        "rnbqkbnr/pppp1ppp/8/8/3pP3/8/PPP2PPP/RNBQKBNR b Kq e3 4 12",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+    ] {
+        assert_eq!(Board::from_fen(fen).unwrap().to_fen(), fen);
+    }
+
+    let double_push = Board::default().make_move(&Move::new(12, 28, false, 0b1));
+    assert_eq!(
+        double_push.to_fen(),
+        "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"
+    );
+}
+
+/// Every position reached from the position survives a FEN round trip
+fn assert_round_trip(board: &Board, depth: usize) {
+    let fen = board.to_fen();
+    let parsed = Board::from_fen(&fen).unwrap();
+    assert_eq!(parsed.to_fen(), fen);
+    // Boards reached by moves can have their piece lists in another order, so the position is
+    // compared field by field
+    assert_eq!(parsed.bitboard, board.bitboard, "{}", fen);
+    assert_eq!(parsed.white_to_move(), board.white_to_move(), "{}", fen);
+    assert_eq!(parsed.castling_bits(), board.castling_bits(), "{}", fen);
+    assert_eq!(
+        parsed.get_en_passant_file(),
+        board.get_en_passant_file(),
+        "{}",
+        fen
+    );
+    assert_eq!(
+        parsed.get_halfmove_clock(),
+        board.get_halfmove_clock(),
+        "{}",
+        fen
+    );
+    assert_eq!(
+        parsed.get_fullmove_counter(),
+        board.get_fullmove_counter(),
+        "{}",
+        fen
+    );
+    // The incrementally updated key matches the one computed from scratch
+    assert_eq!(parsed.zobrist(), board.zobrist(), "{}", fen);
+
+    if depth > 0 {
+        for m in board.generate_legal_moves() {
+            assert_round_trip(&board.make_move(&m), depth - 1);
+        }
+    }
+}
+
+#[test]
+fn fen_round_trip() {
+    for fen in perft_fens() {
+        assert_round_trip(&Board::from_fen(fen).unwrap(), 2);
+    }
+}
+
+fn fen_error(fen: &str) -> FenError {
+    Board::from_fen(fen).unwrap_err()
+}
+
+fn field_and_offset(error: FenError) -> (FenField, usize) {
+    match error {
+        FenError::InvalidField { field, offset, .. } => (field, offset),
+        e => panic!("Expected an invalid field, got {:?}", e),
+    }
+}
+
+#[test]
+fn fen_errors() {
+    use FenField::*;
+
+    assert_eq!(fen_error(""), FenError::FieldCount(0));
+    assert_eq!(fen_error("8/8/8/8/8/8/8/8 w"), FenError::FieldCount(2));
+    assert_eq!(
+        fen_error("4k3/8/8/8/8/8/8/4K3 w - - 0"),
+        FenError::FieldCount(5)
+    );
+    assert_eq!(
+        fen_error("8/8/8/8/8/8/8/8 w - - 0 1"),
+        FenError::InsanePosition(vec![
+            PositionViolation::KingCount {
+                white: true,
+                count: 0
+            },
+            PositionViolation::KingCount {
+                white: false,
+                count: 0
+            },
+        ])
+    );
+
+    let cases = [
+        ("4k3/8/8/8/8/8/8/4X3 w - - 0 1", (PiecePlacement, 17)),
+        ("4k3/8/8/8/8/8/8/4K4 w - - 0 1", (PiecePlacement, 18)),
+        ("4k3/8/8/8/8/8/8/4K2 w - - 0 1", (PiecePlacement, 19)),
+        ("4k3/8/8/8/8/8/4K3 w - - 0 1", (PiecePlacement, 17)),
+        ("4k3/8/8/8/8/8/8/8/4K3 w - - 0 1", (PiecePlacement, 17)),
+        ("4k3/9/8/8/8/8/8/4K3 w - - 0 1", (PiecePlacement, 4)),
+        ("4k3/8/8/8/8/8/8/4K3 white - - 0 1", (SideToMove, 20)),
+        ("4k3/8/8/8/8/8/8/4K3 W - - 0 1", (SideToMove, 20)),
+        ("4k3/8/8/8/8/8/8/4K3 w KX - 0 1", (Castling, 23)),
+        ("4k3/8/8/8/8/8/8/4K3 w KK - 0 1", (Castling, 23)),
+        ("4k3/8/8/8/8/8/8/4K3 w - e9 0 1", (EnPassant, 24)),
+        ("4k3/8/8/8/8/8/8/4K3 w - e3 0 1", (EnPassant, 24)),
+        ("4k3/8/8/8/8/8/8/4K3 b - e6 0 1", (EnPassant, 24)),
+        ("4k3/8/8/8/8/8/8/4K3 w - - x 1", (HalfmoveClock, 26)),
+        ("4k3/8/8/8/8/8/8/4K3 w - - 0 -1", (FullmoveCounter, 28)),
+    ];
+    for (fen, expected) in cases.iter() {
+        assert_eq!(field_and_offset(fen_error(fen)), *expected, "{}", fen);
+    }
+}
+
+#[test]
+fn parse_with_from_str() {
+    let board: Board = FEN_DEFAULT_BOARD.parse().unwrap();
+    assert_eq!(board.to_fen(), FEN_DEFAULT_BOARD);
+    assert!("not a fen".parse::<Board>().is_err());
+}
@@ -0,0 +1,110 @@
+use fisk::board::Board;
+use fisk::game::{Game, GameResult};
+
+fn game(fen: &str) -> Game {
+    Game::new(Board::from_fen(fen).unwrap())
+}
+
+fn play(game: &mut Game, moves: &[&str]) {
+    for text in moves {
+        let mov = game.board().parse_uci_move(text).unwrap();
+        game.make_move(&mov);
+    }
+}
+
+#[test]
+fn threefold_repetition() {
+    let mut game = Game::default();
+    let shuffle = ["g1f3", "g8f6", "f3g1", "f6g8"];
+
+    play(&mut game, &shuffle);
+    assert_eq!(game.repetitions(), 1);
+    assert!(!game.is_repetition());
+    assert_eq!(game.result(), GameResult::Ongoing);
+
+    play(&mut game, &shuffle);
+    assert_eq!(game.repetitions(), 2);
+    assert!(game.is_repetition());
+    assert_eq!(game.result(), GameResult::Repetition);
+    assert!(game.result().is_draw());
+
+    assert_eq!(game.unmake_move().map(|m| m.to_uci()), Some("f6g8".into()));
+    assert!(!game.is_repetition());
+    assert_eq!(game.keys().len(), 7);
+    assert_eq!(game.moves().count(), 7);
+}
+
+#[test]
+fn pawn_moves_end_repetitions() {
+    let mut game = Game::default();
+    play(&mut game, &["g1f3", "g8f6", "f3g1", "f6g8", "e2e3"]);
+    play(&mut game, &["g8f6", "g1f3", "f6g8", "f3g1"]);
+    assert_eq!(game.repetitions(), 1);
+
+    // The same placement with the other side to move is another position
+    let mut game = Game::default();
+    play(&mut game, &["g1f3", "g8f6", "f3g1"]);
+    assert_eq!(game.repetitions(), 0);
+}
+
+#[test]
+fn unmake_restores_board() {
+    let mut game = Game::default();
+    play(&mut game, &["e2e4", "d7d5", "e4d5", "d8d5", "b1c3"]);
+    while game.unmake_move().is_some() {}
+    assert_eq!(*game.board(), Board::default());
+    assert!(game.keys().is_empty());
+}
+
+#[test]
+fn fifty_move_rule() {
+    let mut quiet = game("4k3/8/8/8/8/8/8/Q3K3 w - - 99 80");
+    assert!(!quiet.is_fifty_move_draw());
+    play(&mut quiet, &["a1a2"]);
+    assert!(quiet.is_fifty_move_draw());
+    assert_eq!(quiet.result(), GameResult::FiftyMoveRule);
+
+    // Mate on the hundredth halfmove
+    let mut mate = game("6k1/5ppp/8/8/8/8/8/R5K1 w - - 99 80");
+    play(&mut mate, &["a1a8"]);
+    assert_eq!(mate.result(), GameResult::Checkmate { white_wins: true });
+}
+
+#[test]
+fn mate_and_stalemate() {
+    assert_eq!(
+        game("3RR1k1/5ppp/8/8/8/8/5PPP/6K1 b - - 0 1").result(),
+        GameResult::Checkmate { white_wins: true }
+    );
+    assert_eq!(
+        game("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1").result(),
+        GameResult::Stalemate
+    );
+    assert!(game("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1").result().is_draw());
+    assert_eq!(Game::default().result(), GameResult::Ongoing);
+}
+
+#[test]
+fn insufficient_material() {
+    for fen in [
+        "4k3/8/8/8/8/8/8/4K3 w - - 0 1",
+        "4k3/8/8/8/8/8/8/3NK3 w - - 0 1",
+        "4k3/8/8/8/8/8/8/3BK3 b - - 0 1",
+        "3bk3/8/8/8/8/8/8/2B1K3 w - - 0 1",
+        "4k2b/8/8/8/8/8/8/B1B1K3 w - - 0 1",
+    ] {
+        let game = game(fen);
+        assert!(game.is_insufficient_material(), "{}", fen);
+        assert_eq!(game.result(), GameResult::InsufficientMaterial, "{}", fen);
+    }
+    for fen in [
+        "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1",
+        "4k3/8/8/8/8/8/8/3RK3 w - - 0 1",
+        "4k3/8/8/8/8/8/8/3QK3 w - - 0 1",
+        "4k3/8/8/8/8/8/8/2NNK3 w - - 0 1",
+        "4kn2/8/8/8/8/8/8/3BK3 w - - 0 1",
+        "4k3/8/8/8/8/8/8/2BBK3 w - - 0 1",
+    ] {
+        assert!(!game(fen).is_insufficient_material(), "{}", fen);
+    }
+}
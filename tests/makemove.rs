@@ -1,5 +1,6 @@
 use bitintr::Popcnt;
 use fisk::board::Board;
+use fisk::board::PieceKind::EmptySquare;
 use fisk::constants::SQUARE_NAME;
 use fisk::move_representation::Move;
 
@@ -49,3 +50,43 @@ fn white_kingside_castle() {
     assert!(!b2.can_white_castle_queenside());
     assert_eq!(b2.bitboard.white_king, 1 << 6);
 }
+
+/// kind_at agrees with a scan of the piece list on every square
+fn assert_mailbox_in_sync(board: &Board) {
+    for square in 0..64u8 {
+        let scanned = board
+            .piece_positions_tzcnt
+            .iter()
+            .position(|p| *p == square)
+            .map(|i| board.piece_kinds[i])
+            .unwrap_or(EmptySquare);
+        assert_eq!(
+            board.kind_at(square),
+            scanned,
+            "{}",
+            SQUARE_NAME[square as usize]
+        );
+    }
+}
+
+#[test]
+fn mailbox_follows_moves() {
+    // Castling, en passant and promotions with and without capture
+    let kiwipete = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+    let promotions = "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1";
+    for fen in [kiwipete, promotions] {
+        let mut board = Board::from_fen(fen).unwrap();
+        assert_mailbox_in_sync(&board);
+        for m in board.generate_legal_moves() {
+            let undo = board.make_move_in_place(&m);
+            assert_mailbox_in_sync(&board);
+            for reply in board.generate_legal_moves() {
+                let reply_undo = board.make_move_in_place(&reply);
+                assert_mailbox_in_sync(&board);
+                board.unmake_move(&reply, &reply_undo);
+            }
+            board.unmake_move(&m, &undo);
+            assert_mailbox_in_sync(&board);
+        }
+    }
+}
@@ -1,5 +1,6 @@
 use bitintr::Popcnt;
 
+use fisk::attacks::{bishop_attacks, rook_attacks};
 use fisk::board::Board;
 use fisk::board::PieceKind::EmptySquare;
 use fisk::constants::*;
@@ -149,7 +150,7 @@ fn en_passant() {
 
 #[test]
 fn white_pawn_en_passant_capture() {
-    let s1 = succ("2k5/4p3/8/3P4/8/8/8/1K6 b - g6 0 1");
+    let s1 = succ("2k5/4p3/8/3P4/8/8/8/1K6 b - - 0 1");
     let mut ep_count = 0;
     for s in &s1 {
         if s.get_en_passant_file() != 0 {
@@ -178,7 +179,7 @@ fn pawn_always_promotes() {
 
 #[test]
 fn black_pawn_en_passant_capture() {
-    let s1 = succ("2k5/8/8/8/5p2/8/4P3/1K6 w - g6 0 1");
+    let s1 = succ("2k5/8/8/8/5p2/8/4P3/1K6 w - - 0 1");
     let mut ep_count = 0;
     for s in &s1 {
         if s.get_en_passant_file() != 0 {
@@ -344,22 +345,23 @@ fn rook_file_slide() {
 
 #[test]
 fn rook_row_file_slide() {
+    // The black king on h8 is out of reach of the rooks, the knight on a8 can be captured
     let s1 = gen(
-        "k6R/6R1/5R2/4R3/3R4/2R5/1R6/R6K w - - 0 1",
-        8 * (2 * 7) - 2 + 3,
+        "n6k/6R1/5R2/4R3/3R4/2R5/1R6/R6K w - - 0 1",
+        7 * (2 * 7) - 1 + 3,
     );
 
     let mut capture_count = 0;
     for s in &s1 {
         if s.get_halfmove_clock() == 0 {
             capture_count += 1;
-            assert_eq!(s.bitboard.black_coverage().popcnt(), 0);
-        } else {
             assert_eq!(s.bitboard.black_coverage().popcnt(), 1);
+        } else {
+            assert_eq!(s.bitboard.black_coverage().popcnt(), 2);
         }
         expect_queens(s, 0, 0);
     }
-    assert_eq!(capture_count, 2);
+    assert_eq!(capture_count, 1);
 }
 
 #[test]
@@ -413,8 +415,8 @@ fn black_castling() {
 
 #[test]
 fn pawn_promotion() {
-    gen("2k5/5P2/8/8/8/8/8/K7 w - g6 0 1", 3 + 4);
-    gen("2k2r2/5P2/8/8/8/8/8/K7 w - g6 0 1", 3);
+    gen("2k5/5P2/8/8/8/8/8/K7 w - - 0 1", 3 + 4);
+    gen("2k2r2/5P2/8/8/8/8/8/K7 w - - 0 1", 3);
     gen("2k5/5P2/8/8/8/8/3p4/K7 b - - 0 1", 5 + 4);
     gen("2k5/5P2/8/8/8/8/3p4/3K4 b - - 0 1", 5);
     gen("r3k2r/4P3/8/8/8/8/8/3K4 w q - 0 1", 5);
@@ -427,3 +429,20 @@ fn perft_pos5_regression() {
         44,
     );
 }
+
+#[test]
+fn slider_attacks_stop_at_blockers() {
+    // Rook on d4, blockers on d6, b4 and d1
+    let d4 = 27;
+    let occupancy = (1u64 << 43) | (1u64 << 25) | (1u64 << 3);
+    let rook = (FILE_D & (ROW_2 | ROW_3 | ROW_5 | ROW_6))
+        | (1u64 << 3)
+        | (ROW_4 & (FILE_B | FILE_C | FILE_E | FILE_F | FILE_G | FILE_H));
+    assert_eq!(rook_attacks(d4, occupancy), rook);
+
+    // Bishop on a1 blocked on f6, with an unrelated piece on b1
+    let f6 = 1u64 << 45;
+    let diagonal = (1u64 << 9) | (1u64 << 18) | (1u64 << 27) | (1u64 << 36) | f6;
+    assert_eq!(bishop_attacks(0, f6 | (1u64 << 1)), diagonal);
+    assert_eq!(bishop_attacks(63, 0).popcnt(), 7);
+}
@@ -0,0 +1,147 @@
+use std::collections::HashSet;
+
+use fisk::board::Board;
+use fisk::fen::FEN_DEFAULT_BOARD;
+use fisk::perft::perft;
+
+// https://www.chessprogramming.org/Perft_Results
+const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+const POS3: &str = "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1";
+const POS4: &str = "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1";
+const POS4_MIRROR: &str = "r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1";
+const POS5: &str = "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8";
+const POS6: &str = "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10";
+
+fn fen(fen: &str) -> Board {
+    Board::from_fen(fen).unwrap()
+}
+
+fn assert_perft(fenstr: &str, expected: &[usize]) {
+    let board = fen(fenstr);
+    for (depth, &nodes) in expected.iter().enumerate() {
+        assert_eq!(perft(&board, depth), nodes, "{} depth {}", fenstr, depth);
+    }
+}
+
+#[test]
+fn perft_default_board() {
+    assert_perft(FEN_DEFAULT_BOARD, &[1, 20, 400, 8902, 197_281]);
+}
+
+#[test]
+fn perft_kiwipete() {
+    assert_perft(KIWIPETE, &[1, 48, 2039, 97862]);
+}
+
+#[test]
+fn perft_pos3() {
+    assert_perft(POS3, &[1, 14, 191, 2812, 43238, 674_624]);
+}
+
+#[test]
+fn perft_pos4() {
+    assert_perft(POS4, &[1, 6, 264, 9467, 422_333]);
+    assert_perft(POS4_MIRROR, &[1, 6, 264, 9467, 422_333]);
+}
+
+#[test]
+fn perft_pos5() {
+    assert_perft(POS5, &[1, 44, 1486, 62379]);
+}
+
+#[test]
+fn perft_pos6() {
+    assert_perft(POS6, &[1, 46, 2079, 89890]);
+}
+
+#[test]
+fn perft_en_passant_discovered_check() {
+    // Capturing en passant would uncover a check along the row
+    assert_perft("8/8/8/KPp4r/8/8/8/7k w - c6 0 2", &[1, 4]);
+    assert_perft("8/8/5k2/8/2pP4/8/1B6/K7 b - d3 0 1", &[1, 8]);
+}
+
+/// Legal moves are the pseudo legal moves that don't leave the king in check
+fn assert_same_as_filtered(board: &Board, depth: usize) {
+    let legal: HashSet<u16> = board
+        .generate_legal_moves()
+        .iter()
+        .map(|m| m.to_u16())
+        .collect();
+    let filtered: HashSet<u16> = board
+        .generate_pseudo_legal_moves()
+        .iter()
+        .filter(|m| !board.make_move(m).is_in_check(board.white_to_move()))
+        .map(|m| m.to_u16())
+        .collect();
+    assert_eq!(legal, filtered, "{}", board);
+    assert_eq!(legal.len(), board.generate_legal_moves().len());
+
+    if depth > 0 {
+        for m in board.generate_legal_moves() {
+            assert_same_as_filtered(&board.make_move(&m), depth - 1);
+        }
+    }
+}
+
+#[test]
+fn legal_moves_match_filtered_pseudo_legal_moves() {
+    for fenstr in [
+        FEN_DEFAULT_BOARD,
+        KIWIPETE,
+        POS3,
+        POS4,
+        POS5,
+        POS6,
+        "8/8/8/KPp4r/8/8/8/7k w - c6 0 2",
+        "8/8/5k2/8/2pP4/8/1B6/K7 b - d3 0 1",
+    ] {
+        assert_same_as_filtered(&fen(fenstr), 2);
+    }
+}
+
+/// Perft by copying the board for every move, for comparing with make/unmake
+fn perft_copy_make(board: &Board, depth: usize) -> usize {
+    if depth == 0 {
+        return 1;
+    }
+    board
+        .generate_legal_moves()
+        .iter()
+        .map(|m| perft_copy_make(&board.make_move(m), depth - 1))
+        .sum()
+}
+
+/// Every move is taken back to exactly the board it was made on
+fn assert_unmake_restores(board: &mut Board, depth: usize) {
+    if depth == 0 {
+        return;
+    }
+    let original = *board;
+    for m in board.generate_legal_moves() {
+        let undo = board.make_move_in_place(&m);
+        assert_eq!(*board, original.make_move(&m));
+        assert_unmake_restores(board, depth - 1);
+        board.unmake_move(&m, &undo);
+        assert_eq!(*board, original, "{} after unmaking {}", original, m);
+    }
+}
+
+#[test]
+fn make_unmake_matches_copy_make() {
+    for fenstr in [
+        FEN_DEFAULT_BOARD,
+        KIWIPETE,
+        POS3,
+        POS4,
+        POS4_MIRROR,
+        POS5,
+        POS6,
+        "8/8/8/KPp4r/8/8/8/7k w - c6 0 2",
+        "8/8/5k2/8/2pP4/8/1B6/K7 b - d3 0 1",
+    ] {
+        let board = fen(fenstr);
+        assert_eq!(perft(&board, 3), perft_copy_make(&board, 3), "{}", fenstr);
+        assert_unmake_restores(&mut fen(fenstr), 3);
+    }
+}
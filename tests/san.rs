@@ -0,0 +1,158 @@
+use fisk::board::Board;
+use fisk::perft::perft_fens;
+use fisk::san::SanError;
+
+fn fen(fen: &str) -> Board {
+    Board::from_fen(fen).unwrap()
+}
+
+/// Play the moves from the position, checking that each is written as given
+fn play(board: &Board, moves: &[&str]) -> Board {
+    let mut board = *board;
+    for san in moves {
+        let mov = board.parse_san(san).unwrap();
+        assert_eq!(board.move_to_san(&mov), *san);
+        board = board.make_move(&mov);
+    }
+    board
+}
+
+#[test]
+fn opening_moves() {
+    let board = play(
+        &Board::default(),
+        &["e4", "e5", "Nf3", "Nc6", "Bb5", "a6", "Bxc6", "dxc6", "O-O"],
+    );
+    assert_eq!(
+        board.to_fen(),
+        "r1bqkbnr/1pp2ppp/p1p5/4p3/4P3/5N2/PPPP1PPP/RNBQ1RK1 b kq - 1 5"
+    );
+}
+
+#[test]
+fn check_and_mate() {
+    let board = play(&Board::default(), &["f3", "e5", "g4", "Qh4#"]);
+    assert!(board.generate_legal_moves().is_empty());
+
+    play(
+        &fen("4k3/8/8/8/8/8/8/R3K3 w Q - 0 1"),
+        &["O-O-O", "Kf7", "Rd7+"],
+    );
+}
+
+#[test]
+fn disambiguation() {
+    // Knights on b1 and f1 reach d2, rooks on a1 and a5 reach a3
+    let board = fen("4k3/8/8/R7/8/8/8/RN2KN2 w - - 0 1");
+    play(&board, &["Nbd2"]);
+    play(&board, &["Nfd2"]);
+    play(&board, &["R1a3"]);
+    play(&board, &["R5a3"]);
+    assert_eq!(
+        board.parse_san("Nd2"),
+        Err(SanError::Ambiguous("Nd2".into()))
+    );
+
+    // Three queens reaching e4 need both file and rank for the one on h4
+    let board = fen("4k3/8/8/8/7Q/8/8/4K2Q w - - 0 1");
+    play(&board, &["Q4e4+"]);
+    let board = fen("4k3/8/8/8/1Q5Q/8/8/4K2Q w - - 0 1");
+    play(&board, &["Qh4e4+"]);
+
+    // Only legal moves need disambiguation, the knight on e4 is pinned
+    play(
+        &fen("4k3/4r3/8/8/4N3/8/8/4KN2 w - - 0 1"),
+        &["Nd2", "Rxe4+"],
+    );
+}
+
+#[test]
+fn pawn_moves() {
+    // En passant
+    play(
+        &fen("rnbqkbnr/1ppp1ppp/p7/3Pp3/8/8/PPP1PPPP/RNBQKBNR w KQkq e6 0 1"),
+        &["dxe6"],
+    );
+
+    // Promotions, with and without captures
+    let board = fen("1r2k3/P7/8/8/8/8/8/4K3 w - - 0 1");
+    play(&board, &["a8=Q"]);
+    play(&board, &["axb8=N"]);
+    play(&board, &["axb8=Q+"]);
+    assert_eq!(
+        board.parse_san("a8Q"),
+        board.parse_san("a8=Q"),
+        "The promotion sign is optional"
+    );
+    assert!(
+        board.parse_san("a8").is_err(),
+        "Promotion piece is required"
+    );
+}
+
+#[test]
+fn lenient_parsing() {
+    let board = Board::default();
+    for (san, canonical) in [
+        ("e4!", "e4"),
+        ("Nf3?!", "Nf3"),
+        (" d4 ", "d4"),
+        ("Ng1f3", "Nf3"),
+        ("Nxf3", "Nf3"),
+    ] {
+        let mov = board.parse_san(san).unwrap();
+        assert_eq!(board.move_to_san(&mov), canonical, "{}", san);
+    }
+}
+
+#[test]
+fn san_errors() {
+    let board = Board::default();
+    for san in ["", "e", "e9", "Zf3", "Nf3g", "xx"] {
+        assert_eq!(
+            board.parse_san(san),
+            Err(SanError::Malformed(san.into())),
+            "{}",
+            san
+        );
+    }
+    for san in ["e5", "Nf4", "O-O", "Ke2", "Qd1"] {
+        assert_eq!(
+            board.parse_san(san),
+            Err(SanError::NoMatchingMove(san.into())),
+            "{}",
+            san
+        );
+    }
+}
+
+/// Every legal move has a unique SAN that parses back to the move
+fn assert_san_round_trip(board: &Board, depth: usize) {
+    let moves = board.generate_legal_moves();
+    let mut sans: Vec<String> = moves.iter().map(|m| board.move_to_san(m)).collect();
+    for (m, san) in moves.iter().zip(sans.iter()) {
+        assert_eq!(
+            board.parse_san(san),
+            Ok(*m),
+            "{} in {}",
+            san,
+            board.to_fen()
+        );
+    }
+    sans.sort();
+    sans.dedup();
+    assert_eq!(sans.len(), moves.len(), "{}", board.to_fen());
+
+    if depth > 0 {
+        for m in moves {
+            assert_san_round_trip(&board.make_move(&m), depth - 1);
+        }
+    }
+}
+
+#[test]
+fn san_round_trip() {
+    for fen in perft_fens() {
+        assert_san_round_trip(&Board::from_fen(fen).unwrap(), 1);
+    }
+}
@@ -0,0 +1,353 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use fisk::board::Board;
+use fisk::eval::MATE;
+use fisk::game::Game;
+use fisk::search::{Search, SearchFeatures};
+use fisk::transposition::TranspositionTable;
+
+fn fen(fen: &str) -> Board {
+    Board::from_fen(fen).unwrap()
+}
+
+fn assert_pv_is_legal(board: &Board, pv: &[fisk::move_representation::Move]) {
+    let mut b = *board;
+    for m in pv {
+        assert!(b.generate_pseudo_legal_moves().contains(m));
+        let white = b.white_to_move();
+        b.make_move_in_place(m);
+        assert!(!b.is_in_check(white));
+    }
+}
+
+#[test]
+fn iterative_deepening_returns_pv() {
+    let board = fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+    let tt = TranspositionTable::new(1);
+    let result = Search::new(&tt).iterative_deepening(&board, 4);
+
+    assert_eq!(result.depth, 4);
+    assert!(!result.pv.is_empty());
+    assert!(result.pv.len() <= 4);
+    assert_eq!(result.best_move(), result.pv.first().copied());
+    assert_pv_is_legal(&board, &result.pv);
+}
+
+#[test]
+fn iterative_deepening_matches_fixed_depth_score() {
+    let board = Board::default();
+    let tt = TranspositionTable::new(1);
+    let result = Search::new(&tt).iterative_deepening(&board, 3);
+    assert_eq!(result.score, board.best_move(3).0);
+}
+
+#[test]
+fn stopped_search_completes_first_iteration() {
+    let board = Board::default();
+    let tt = TranspositionTable::new(1);
+    let stop = Arc::new(AtomicBool::new(true));
+    let result = Search::new(&tt)
+        .with_stop_flag(stop)
+        .iterative_deepening(&board, 10);
+
+    assert_eq!(result.depth, 1);
+    assert!(result.best_move().is_some());
+}
+
+#[test]
+fn search_can_be_stopped_inside_iteration() {
+    let board = fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+    let tt = TranspositionTable::new(1);
+    let stop = Arc::new(AtomicBool::new(false));
+
+    let stopper = {
+        let stop = stop.clone();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(200));
+            stop.store(true, Ordering::Relaxed);
+        })
+    };
+    let result = Search::new(&tt)
+        .with_stop_flag(stop)
+        .iterative_deepening(&board, 64);
+    stopper.join().unwrap();
+
+    assert!(result.depth >= 1 && result.depth < 64);
+    assert_pv_is_legal(&board, &result.pv);
+}
+
+#[test]
+fn score_is_from_side_to_move() {
+    // The side to move wins a free queen
+    let (white_score, _) = fen("3q2k1/8/8/8/8/8/8/3Q2K1 w - - 0 1").best_move(2);
+    let (black_score, _) = fen("3q2k1/8/8/8/8/8/8/3Q2K1 b - - 0 1").best_move(2);
+    assert!(white_score > 500, "{}", white_score);
+    assert_eq!(black_score, white_score);
+
+    let (mated, best) = fen("k7/8/1K6/8/8/8/8/7R b - - 0 1").best_move(3);
+    assert_eq!(mated, -(MATE - 2));
+    assert!(best.is_some());
+}
+
+#[test]
+fn no_legal_moves_gives_empty_pv() {
+    let mated = fen("3RR1k1/5ppp/8/8/8/8/5PPP/6K1 b - - 0 1");
+    let tt = TranspositionTable::new(1);
+    let result = Search::new(&tt).iterative_deepening(&mated, 3);
+    assert!(result.pv.is_empty());
+    assert!(result.best_move().is_none());
+}
+
+fn assert_avoids(fenstr: &str, depth: usize, bad_move: &str) {
+    let (_, best) = fen(fenstr).best_move(depth);
+    let best = best.unwrap();
+    assert_ne!(best.to_string(), bad_move, "{}", fenstr);
+}
+
+fn assert_plays(fenstr: &str, depth: usize, good_move: &str) {
+    let (_, best) = fen(fenstr).best_move(depth);
+    assert_eq!(best.unwrap().to_string(), good_move, "{}", fenstr);
+}
+
+#[test]
+fn quiescence_avoids_defended_captures() {
+    // Capturing wins material statically, but the recapture loses the queen
+    assert_avoids("4k3/8/4p3/3n4/8/8/8/3QK3 w - - 0 1", 1, "d1xd5");
+    assert_avoids("k7/8/8/3p4/4p3/8/8/K3Q3 w - - 0 1", 1, "e1xe4");
+    assert_avoids("3qk3/8/8/3N4/4P3/8/8/4K3 b - - 0 1", 1, "d8xd5");
+    assert_avoids("4k3/8/8/2b5/8/2P1P3/3R4/4K3 w - - 0 1", 2, "d2d5");
+}
+
+#[test]
+fn quiescence_still_takes_free_material() {
+    assert_plays("k7/8/8/8/4p3/8/8/K3Q3 w - - 0 1", 1, "e1xe4");
+    assert_plays("4k3/8/8/3n4/8/8/8/3QK3 w - - 0 1", 1, "d1xd5");
+}
+
+#[test]
+fn quiescence_does_not_stand_pat_in_check() {
+    // The back rank mate is only found at depth 1 if the mated side can't stand pat
+    let (score, best) = fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").best_move(1);
+    assert_eq!(score, MATE - 1);
+    assert_eq!(best.unwrap().to_string(), "a1a8");
+}
+
+#[test]
+fn quiescence_sees_hanging_piece_after_quiet_move() {
+    // At depth 1 the queen must not step onto a square attacked by a pawn
+    assert_avoids("4k3/8/2p5/8/8/8/8/3QK3 w - - 0 1", 1, "d1d5");
+    assert_avoids("4k3/8/2p5/8/8/8/8/3QK3 w - - 0 1", 1, "d1b5");
+}
+
+#[test]
+fn perpetual_check_is_a_draw() {
+    // Black is far ahead in material, but can't escape the checks from h5 and e8
+    let board = fen("1n4k1/q5p1/8/7Q/r7/1r6/5PPP/6K1 w - - 0 1");
+    let (score, best) = Game::new(board).best_move(4);
+    assert_eq!(score, 0);
+    assert_eq!(best.unwrap().to_string(), "h5e8");
+    assert_eq!(board.best_move(4).0, 0, "Repetitions within the search");
+}
+
+#[test]
+fn repetition_of_game_history_is_a_draw() {
+    let board = fen("1n4k1/q5p1/8/7Q/r7/1r6/5PPP/6K1 w - - 0 1");
+    let mut game = Game::new(board);
+    for text in ["h5e8", "g8h7", "e8h5", "h7g8"] {
+        let mov = game.board().parse_uci_move(text).unwrap();
+        game.make_move(&mov);
+    }
+    assert_eq!(game.board().zobrist(), board.zobrist());
+
+    // Too shallow to see the perpetual, but Qe8+ repeats a position of the game
+    assert!(board.best_move(2).0 < 0);
+    let tt = TranspositionTable::new(1);
+    let result = Search::new(&tt)
+        .with_history(game.keys())
+        .iterative_deepening(game.board(), 2);
+    assert_eq!(result.score, 0);
+    assert_eq!(result.best_move().unwrap().to_string(), "h5e8");
+}
+
+#[test]
+fn fifty_move_rule_is_a_draw() {
+    // Every move is quiet and reaches the hundredth halfmove
+    assert_eq!(fen("4k3/8/8/8/8/8/8/Q3K3 w - - 99 80").best_move(3).0, 0);
+    // Unless it mates
+    let (score, best) = fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 99 80").best_move(3);
+    assert!(score > 0);
+    assert_eq!(best.unwrap().to_string(), "a1a8");
+}
+
+#[test]
+fn quiet_move_ordering_saves_nodes() {
+    let board = Board::default();
+    let search = |features| {
+        let tt = TranspositionTable::new(1);
+        let mut search = Search::new(&tt).with_features(features);
+        let result = search.iterative_deepening(&board, 5);
+        (result.score, search.nodes())
+    };
+
+    // What gets pruned depends on the move order, without pruning the score is the same
+    let unpruned = SearchFeatures {
+        null_move: false,
+        late_move_reductions: false,
+        futility_pruning: false,
+        reverse_futility_pruning: false,
+        ..SearchFeatures::default()
+    };
+    let (score, nodes) = search(unpruned);
+    let (unordered_score, unordered_nodes) = search(SearchFeatures {
+        quiet_move_ordering: false,
+        ..unpruned
+    });
+    assert_eq!(score, unordered_score);
+    assert!(nodes < unordered_nodes, "{} >= {}", nodes, unordered_nodes);
+
+    // With pruning the scores may differ, but the ordering should still save nodes
+    let all = SearchFeatures::default();
+    let (_, nodes) = search(all);
+    let (_, unordered_nodes) = search(SearchFeatures {
+        quiet_move_ordering: false,
+        ..all
+    });
+    assert!(nodes < unordered_nodes, "{} >= {}", nodes, unordered_nodes);
+}
+
+#[test]
+fn pvs_saves_nodes() {
+    let search = |board: &Board, features| {
+        let tt = TranspositionTable::new(1);
+        let mut search = Search::new(&tt).with_features(features);
+        let result = search.iterative_deepening(board, 5);
+        (result.best_move(), search.nodes())
+    };
+
+    // Re-searches can cost more than they save in a single sharp position, so the nodes are
+    // compared over all of them
+    let all = SearchFeatures::default();
+    let (mut nodes, mut full_window_nodes) = (0, 0);
+    for fenstr in [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+    ] {
+        let board = fen(fenstr);
+        let (best, pvs_nodes) = search(&board, all);
+        let (full_window_best, window_nodes) = search(&board, SearchFeatures { pvs: false, ..all });
+        assert_eq!(best, full_window_best, "{}", fenstr);
+        nodes += pvs_nodes;
+        full_window_nodes += window_nodes;
+    }
+    assert!(
+        nodes < full_window_nodes,
+        "{} >= {}",
+        nodes,
+        full_window_nodes
+    );
+}
+
+#[test]
+fn null_move_pruning_finds_zugzwang_move() {
+    // Rf1 wins by putting black in zugzwang, which a null move search can't see. Found with and
+    // without verifying null move cutoffs.
+    let board = fen("8/8/p1p5/1p5p/1P5p/8/PPP2K1p/4R1rk w - - 0 1");
+    let all = SearchFeatures::default();
+    for features in [
+        all,
+        SearchFeatures {
+            null_move_verification: false,
+            ..all
+        },
+    ] {
+        let tt = TranspositionTable::new(1);
+        let result = Search::new(&tt)
+            .with_features(features)
+            .iterative_deepening(&board, 10);
+        assert_eq!(
+            result.best_move().unwrap().to_uci(),
+            "e1f1",
+            "{:?}",
+            features
+        );
+    }
+}
+
+#[test]
+fn no_null_move_with_only_pawns() {
+    // Neither side has pieces to pass with, so the search is the same as without null moves
+    let board = fen("4k3/pp4pp/8/8/8/8/PP4PP/4K3 w - - 0 1");
+    let search = |features| {
+        let tt = TranspositionTable::new(1);
+        let mut search = Search::new(&tt).with_features(features);
+        let result = search.iterative_deepening(&board, 8);
+        (result.best_move(), result.score, search.nodes())
+    };
+
+    let all = SearchFeatures::default();
+    assert_eq!(
+        search(all),
+        search(SearchFeatures {
+            null_move: false,
+            ..all
+        })
+    );
+}
+
+#[test]
+fn pruning_saves_nodes() {
+    let board = fen("r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10");
+    let search = |features| {
+        let tt = TranspositionTable::new(1);
+        let mut search = Search::new(&tt).with_features(features);
+        search.iterative_deepening(&board, 5);
+        search.nodes()
+    };
+
+    let all = SearchFeatures::default();
+    let nodes = search(all);
+    for (name, features) in [
+        (
+            "null move",
+            SearchFeatures {
+                null_move: false,
+                ..all
+            },
+        ),
+        (
+            "late move reductions",
+            SearchFeatures {
+                late_move_reductions: false,
+                ..all
+            },
+        ),
+        (
+            "futility pruning",
+            SearchFeatures {
+                futility_pruning: false,
+                ..all
+            },
+        ),
+        (
+            "reverse futility pruning",
+            SearchFeatures {
+                reverse_futility_pruning: false,
+                ..all
+            },
+        ),
+    ] {
+        let unpruned_nodes = search(features);
+        assert!(
+            nodes < unpruned_nodes,
+            "{}: {} >= {}",
+            name,
+            nodes,
+            unpruned_nodes
+        );
+    }
+}
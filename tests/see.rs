@@ -0,0 +1,53 @@
+use fisk::board::Board;
+
+/// Position, move in UCI notation and the material won by it
+const SEE_POSITIONS: [(&str, &str, i32); 13] = [
+    // Undefended pawn
+    (
+        "1k1r4/1pp4p/p7/4p3/8/P5P1/1PP4P/2K1R3 w - - 0 1",
+        "e1e5",
+        100,
+    ),
+    // Defenders and attackers alternate, the queen joins behind the bishop
+    (
+        "1k1r3q/1ppn3p/p4b2/4p3/8/P2N2P1/1PP1R1BP/2K1Q3 w - - 0 1",
+        "d3e5",
+        -220,
+    ),
+    ("4k3/8/2p5/3p4/8/8/8/3QK3 w - - 0 1", "d1d5", -800),
+    ("4k3/8/2p5/3n4/8/4N3/8/4K3 w - - 0 1", "e3d5", 0),
+    ("4k3/8/4p3/3p4/4P3/8/8/4K3 w - - 0 1", "e4d5", 0),
+    // The rook behind the first rook recaptures
+    ("4r1k1/8/8/4p3/8/8/4R3/4R1K1 w - - 0 1", "e2e5", 100),
+    // The king can only recapture an undefended piece
+    ("8/8/3k4/4p3/8/8/8/4R1K1 w - - 0 1", "e1e5", -400),
+    ("8/8/3k4/4p3/8/8/4R3/4R1K1 w - - 0 1", "e2e5", 100),
+    ("4k3/8/8/3Pp3/8/8/8/4K3 w - e6 0 1", "d5e6", 100),
+    // Promotions
+    ("1r2k3/P7/8/8/8/8/8/4K3 w - - 0 1", "a7a8q", -100),
+    ("1r2k3/P7/8/8/8/8/8/4K3 w - - 0 1", "a7b8q", 1300),
+    // Quiet moves
+    ("4k3/8/2p5/8/8/8/8/3QK3 w - - 0 1", "d1d5", -900),
+    ("4k3/8/2p5/8/8/8/8/3QK3 w - - 0 1", "d1d4", 0),
+];
+
+#[test]
+fn static_exchange_evaluation() {
+    for (fen, uci_move, expected) in SEE_POSITIONS.iter() {
+        let board = Board::from_fen(fen).unwrap();
+        let m = board.parse_uci_move(uci_move).unwrap();
+        assert_eq!(board.see(&m), *expected, "{} {}", fen, uci_move);
+    }
+}
+
+#[test]
+fn black_to_move() {
+    let board = Board::from_fen("3qk3/8/8/3N4/4P3/8/8/4K3 b - - 0 1").unwrap();
+    let m = board.parse_uci_move("d8d5").unwrap();
+    assert_eq!(board.see(&m), 320 - 900);
+
+    // The rook behind the first rook recaptures
+    let board = Board::from_fen("4r1k1/4r3/8/8/4P3/8/8/4R1K1 b - - 0 1").unwrap();
+    let m = board.parse_uci_move("e7e4").unwrap();
+    assert_eq!(board.see(&m), 100);
+}
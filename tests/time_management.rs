@@ -0,0 +1,128 @@
+use std::time::{Duration, Instant};
+
+use fisk::board::Board;
+use fisk::search::Search;
+use fisk::time_management::*;
+use fisk::transposition::TranspositionTable;
+use vampirc_uci::UciTimeControl;
+
+fn ms(millis: u64) -> Duration {
+    Duration::from_millis(millis)
+}
+
+fn uci_ms(millis: i64) -> Option<time::Duration> {
+    Some(time::Duration::milliseconds(millis))
+}
+
+#[test]
+fn move_time_subtracts_overhead() {
+    let limits = TimeLimits::move_time(ms(1000), ms(50));
+    assert_eq!(limits.soft, Some(ms(950)));
+    assert_eq!(limits.hard, Some(ms(950)));
+
+    // Always leave some time to complete the first iteration
+    let limits = TimeLimits::move_time(ms(10), ms(50));
+    assert_eq!(limits.hard, Some(ms(1)));
+}
+
+#[test]
+fn clock_limits_stay_within_remaining_time() {
+    for (time, inc, mtg) in [
+        (60_000, 0, None),
+        (60_000, 1000, None),
+        (1000, 5000, None),
+        (60_000, 0, Some(1)),
+        (60_000, 0, Some(40)),
+        (20, 0, None),
+    ] {
+        let limits = TimeLimits::from_clock(ms(time), ms(inc), mtg, ms(30));
+        let (soft, hard) = (limits.soft.unwrap(), limits.hard.unwrap());
+        assert!(soft <= hard, "{} {} {:?}", time, inc, mtg);
+        assert!(hard < ms(time).max(ms(2)), "{} {} {:?}", time, inc, mtg);
+    }
+}
+
+#[test]
+fn clock_limits_use_more_time_with_fewer_moves_to_go() {
+    let many = TimeLimits::from_clock(ms(60_000), ms(0), Some(40), ms(30));
+    let few = TimeLimits::from_clock(ms(60_000), ms(0), Some(5), ms(30));
+    let increment = TimeLimits::from_clock(ms(60_000), ms(2000), Some(40), ms(30));
+    assert!(few.soft > many.soft);
+    assert!(increment.soft > many.soft);
+}
+
+#[test]
+fn uci_time_control_uses_side_to_move_clock() {
+    let tc = UciTimeControl::TimeLeft {
+        white_time: uci_ms(100_000),
+        black_time: uci_ms(10_000),
+        white_increment: None,
+        black_increment: None,
+        moves_to_go: None,
+    };
+    let white = TimeLimits::from_uci(&tc, true, ms(0)).unwrap();
+    let black = TimeLimits::from_uci(&tc, false, ms(0)).unwrap();
+    assert_eq!(
+        white,
+        TimeLimits::from_clock(ms(100_000), ms(0), None, ms(0))
+    );
+    assert!(black.soft < white.soft);
+
+    assert!(
+        !TimeLimits::from_uci(&UciTimeControl::Infinite, true, ms(0))
+            .unwrap()
+            .is_limited()
+    );
+    assert!(!TimeLimits::from_uci(&UciTimeControl::Ponder, true, ms(0))
+        .unwrap()
+        .is_limited());
+    assert_eq!(
+        TimeLimits::from_uci(
+            &UciTimeControl::MoveTime(time::Duration::seconds(2)),
+            true,
+            ms(0)
+        ),
+        Some(TimeLimits::move_time(ms(2000), ms(0)))
+    );
+}
+
+#[test]
+fn uci_time_control_without_own_clock_is_no_time_control() {
+    let tc = UciTimeControl::TimeLeft {
+        white_time: None,
+        black_time: uci_ms(1000),
+        white_increment: None,
+        black_increment: None,
+        moves_to_go: None,
+    };
+    assert_eq!(TimeLimits::from_uci(&tc, true, ms(0)), None);
+    assert!(TimeLimits::from_uci(&tc, false, ms(0)).is_some());
+}
+
+#[test]
+fn search_respects_hard_limit() {
+    let board = Board::default();
+    let tt = TranspositionTable::new(1);
+    let start = Instant::now();
+    let result = Search::new(&tt)
+        .with_time_limits(TimeLimits::move_time(ms(200), ms(0)))
+        .iterative_deepening(&board, 64);
+
+    assert!(result.depth >= 1 && result.depth < 64);
+    assert!(result.best_move().is_some());
+    assert!(start.elapsed() < ms(1000));
+}
+
+#[test]
+fn search_stops_iterating_after_soft_limit() {
+    let board = Board::default();
+    let tt = TranspositionTable::new(1);
+    let limits = TimeLimits {
+        soft: Some(ms(0)),
+        hard: None,
+    };
+    let result = Search::new(&tt)
+        .with_time_limits(limits)
+        .iterative_deepening(&board, 64);
+    assert_eq!(result.depth, 1);
+}
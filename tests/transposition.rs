@@ -0,0 +1,97 @@
+use fisk::board::Board;
+use fisk::eval::MATE;
+use fisk::move_representation::Move;
+use fisk::search::Search;
+use fisk::transposition::*;
+
+#[test]
+fn size_is_power_of_two() {
+    for size_mb in [1, 3, 16, 100] {
+        let tt = TranspositionTable::new(size_mb);
+        assert!(tt.len().is_power_of_two());
+        assert!(tt.len() * 16 <= size_mb * 1024 * 1024);
+    }
+}
+
+#[test]
+fn store_and_probe() {
+    let tt = TranspositionTable::new(1);
+    let key = 0xDEAD_BEEF_1234_5678;
+    assert!(tt.probe(key).is_none());
+
+    let mov = Move::new(12, 28, false, 0b1);
+    tt.store(key, 7, Bound::Lower, -350, Some(mov));
+    let entry = tt.probe(key).unwrap();
+    assert_eq!(entry.depth, 7);
+    assert_eq!(entry.bound, Bound::Lower);
+    assert_eq!(entry.score, -350);
+    assert_eq!(entry.best_move, Some(mov));
+
+    // Same index, different key
+    assert!(tt.probe(key ^ (1 << 63)).is_none());
+}
+
+#[test]
+fn shallow_entry_keeps_deep_entry_from_same_search() {
+    let mut tt = TranspositionTable::new(1);
+    let key = 42;
+    let other_key = key | (1 << 62); // Maps to the same slot
+
+    tt.store(key, 8, Bound::Upper, 10, None);
+    tt.store(other_key, 2, Bound::Upper, 20, None);
+    assert_eq!(tt.probe(key).unwrap().depth, 8);
+    assert!(tt.probe(other_key).is_none());
+
+    // Entries from an earlier search are replaced
+    tt.new_search();
+    tt.store(other_key, 2, Bound::Upper, 20, None);
+    assert!(tt.probe(key).is_none());
+    assert_eq!(tt.probe(other_key).unwrap().score, 20);
+}
+
+#[test]
+fn mate_scores_are_stored_relative_to_node() {
+    let mate_in_3_from_root = MATE - 5;
+    let stored = score_to_tt(mate_in_3_from_root, 4);
+    assert_eq!(stored, MATE - 1);
+    assert_eq!(score_from_tt(stored, 4), mate_in_3_from_root);
+    assert_eq!(score_from_tt(stored, 2), MATE - 3);
+
+    let mated = -MATE + 6;
+    assert_eq!(score_from_tt(score_to_tt(mated, 3), 3), mated);
+    assert_eq!(score_to_tt(150, 10), 150);
+}
+
+#[test]
+fn search_result_is_stable_with_warm_table() {
+    let board =
+        Board::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1")
+            .unwrap();
+    let tt = TranspositionTable::new(1);
+    let cold = Search::new(&tt).iterative_deepening(&board, 3);
+    let warm = Search::new(&tt).iterative_deepening(&board, 3);
+    assert_eq!(cold.score, warm.score);
+    assert_eq!(cold.score, board.best_move(3).0);
+}
+
+#[test]
+fn searches_on_a_shared_table_keep_its_age() {
+    // Lazy SMP threads search the same position in one table, entries stored by one thread
+    // must stay current for the others
+    let board = Board::default();
+    let tt = TranspositionTable::new(1);
+    Search::new(&tt).iterative_deepening(&board, 5);
+    let hashfull = tt.hashfull();
+    assert!(hashfull > 0);
+    Search::new(&tt).iterative_deepening(&board, 5);
+    assert!(tt.hashfull() >= hashfull);
+}
+
+#[test]
+fn finds_shortest_mate() {
+    // Back rank mate in one, with a slower mate also available
+    let board = Board::from_fen("6k1/5ppp/8/8/8/8/5PPP/3RR1K1 w - - 0 1").unwrap();
+    let (score, best) = board.best_move(4);
+    assert_eq!(score, MATE - 1);
+    assert!(best.is_some());
+}
@@ -1,4 +1,6 @@
-use fisk::uci::UciState;
+use fisk::board::Board;
+use fisk::move_representation::{IllegalMoveError, Move};
+use fisk::uci::{UciError, UciState};
 
 fn uci_test(state: &mut UciState, input: &str, expected_output: &str) {
     let mut out_buf: Vec<u8> = Vec::new();
@@ -6,7 +8,17 @@ fn uci_test(state: &mut UciState, input: &str, expected_output: &str) {
         .run_uci_input(&mut input.as_bytes(), &mut out_buf)
         .unwrap();
 
-    assert_eq!(&out_buf, expected_output.as_bytes());
+    assert_eq!(without_info(&out_buf), expected_output);
+}
+
+/// Output with the info lines removed, as these depend on timing
+fn without_info(output: &[u8]) -> String {
+    String::from_utf8(output.to_vec())
+        .unwrap()
+        .lines()
+        .filter(|line| !line.starts_with("info "))
+        .map(|line| format!("{}\n", line))
+        .collect()
 }
 
 #[test]
@@ -14,7 +26,21 @@ fn engine_replies_to_uci() {
     uci_test(
         &mut UciState::new(),
         &"uci\n",
-        "id name fisk\nid author Aksel Slettemark\nuciok\n",
+        "id name fisk\n\
+         id author Aksel Slettemark\n\
+         option name Hash type spin default 16 min 1 max 32768\n\
+         option name Threads type spin default 1 min 1 max 256\n\
+         option name MultiPV type spin default 1 min 1 max 256\n\
+         option name Move Overhead type spin default 30 min 0 max 5000\n\
+         option name Debug type check default true\n\
+         option name Quiet Move Ordering type check default true\n\
+         option name Null Move Pruning type check default true\n\
+         option name Null Move Verification type check default true\n\
+         option name Late Move Reductions type check default true\n\
+         option name Futility Pruning type check default true\n\
+         option name Reverse Futility Pruning type check default true\n\
+         option name Principal Variation Search type check default true\n\
+         uciok\n",
     );
 }
 
@@ -27,11 +53,26 @@ fn engine_finds_obvious_move() {
     );
 }
 
+#[test]
+fn go_without_own_clock_searches_default_depth() {
+    // Only black's clock is given with white to move, so there is no time control
+    uci_test(
+        &mut UciState::new(),
+        &"position fen 6k1/8/6K1/8/8/8/8/4R3 w - - 0 1\ngo btime 1000\n",
+        "bestmove e1e8\n",
+    );
+}
+
 #[test]
 fn engine_interprets_castling() {
     let mut state = UciState::new();
     let mut out_buf: Vec<u8> = Vec::new();
     state.run_uci_input(&mut "position startpos moves e2e3 d7d5 b1c3 g8f6 g1f3 e7e6 f1b5 b8d7 f3e5 f8e7 d2d4 e8g8 e1g1 f6e4 d1f3 a7a6 f3f7 f8f7\n".as_bytes(), &mut out_buf).unwrap();
+    assert!(
+        out_buf.is_empty(),
+        "{}",
+        String::from_utf8(out_buf).unwrap()
+    );
 }
 
 #[test]
@@ -39,6 +80,11 @@ fn engine_interprets_promotion() {
     let mut state = UciState::new();
     let mut out_buf: Vec<u8> = Vec::new();
     state.run_uci_input(&mut "position startpos moves e2e4 d7d5 e4d5 d8d5 b1c3 d5e6 f1e2 b8c6 g1f3 g8f6 e1g1 e6d6 a2a3 a7a6 d2d4 c8g4 d4d5 c6b8 f3d2 d6d7 f2f3 g4h5 d2c4 b7b5 c4e5 d7d6 c1f4 b8d7 d1d4 d6b6 f1d1 b6d4 d1d4 g7g5 e5d7 f6d7 f4g5 h8g8 g5f4 e8c8 g2g4 h5g6 d4d1 g6c2 d1c1 c2f5 c3b5 e7e5 d5e6 f8c5 g1f1 f7e6 b5c7 e6e5 c7a6 f5g4 f3g4 g8f8 a6c5 f8f4 f1g1 d7c5 c1c5 c8b8 c5b5 b8a8 h2h3 d8d2 a1e1 a8a7 b2b4 e5e4 e1f1 f4f1 g1f1 d2a2 b5a5 a7b6 f1f2 b6c6 f2e3 a2c2 h3h4 c6d6 g4g5 c2c7 e2g4 c7c4 h4h5 c4c7 h5h6 c7c4 a5a7 d6d5 g5g6 c4c3 e3f2 c3c2 f2g3 h7g6 a7a5 d5d4 h6h7 c2c3 g3h4 c3c1 h7h8q\n".as_bytes(), &mut out_buf).unwrap();
+    assert!(
+        out_buf.is_empty(),
+        "{}",
+        String::from_utf8(out_buf).unwrap()
+    );
 }
 
 #[test]
@@ -47,3 +93,420 @@ fn engine_promotes_uci() {
     &"position startpos moves e2e3 b8c6 b1c3 e7e5 g1f3 g8f6 f1b5 d7d6 d2d3 c8d7 b5c4 f8e7 e1g1 e8g8 f3g5 h7h6 c4f7 f8f7 g5f7 g8f7 f2f4 e5f4 f1f4 f7g8 e3e4 c6e5 d3d4 e5g6 f4f2 f6g4 f2f3 g6h4 f3g3 h6h5 d1d3 d8f8 c1e3 g4e3 d3e3 f8f6 c3d5 f6f7 e3b3 d7e6 b3b7 a8f8 d5e7 f7e7 b7a7 e7f6 d4d5 e6g4 g3b3 g4e2 b3b8 f6f1 a1f1 e2f1 b8f8 g8f8 g1f1 h4g6 a7c7 g6e5 c7d6 f8f7 d6e5 g7g6 d5d6 g6g5 d6d7 g5g4\ngo\n",
     "bestmove d7d8q\n");
 }
+
+#[test]
+fn engine_answers_within_movetime() {
+    let mut state = UciState::new();
+    let mut out_buf: Vec<u8> = Vec::new();
+    let start = std::time::Instant::now();
+    state
+        .run_uci_input(
+            &mut "position startpos\ngo movetime 300\n".as_bytes(),
+            &mut out_buf,
+        )
+        .unwrap();
+    assert!(start.elapsed() < std::time::Duration::from_millis(1500));
+    assert!(without_info(&out_buf).starts_with("bestmove "));
+}
+
+fn uci_output(state: &mut UciState, input: &str) -> String {
+    let mut out_buf: Vec<u8> = Vec::new();
+    state
+        .run_uci_input(&mut input.as_bytes(), &mut out_buf)
+        .unwrap();
+    String::from_utf8(out_buf).unwrap()
+}
+
+#[test]
+fn engine_answers_isready_and_stop_while_searching() {
+    let start = std::time::Instant::now();
+    let output = without_info(
+        uci_output(
+            &mut UciState::new(),
+            "position startpos\ngo infinite\nisready\nstop\n",
+        )
+        .as_bytes(),
+    );
+    assert!(start.elapsed() < std::time::Duration::from_secs(5));
+    assert!(output.starts_with("readyok\nbestmove "), "{}", output);
+    assert_eq!(output.lines().count(), 2);
+}
+
+/// Input that arrives line by line, each after a delay
+struct DelayedInput {
+    lines: Vec<(u64, &'static str)>,
+}
+
+impl std::io::Read for DelayedInput {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.lines.is_empty() {
+            return Ok(0);
+        }
+        let (delay_ms, line) = self.lines.remove(0);
+        std::thread::sleep(std::time::Duration::from_millis(delay_ms));
+        buf[..line.len()].copy_from_slice(line.as_bytes());
+        Ok(line.len())
+    }
+}
+
+#[test]
+fn ponderhit_continues_search_with_clock() {
+    let mut input = DelayedInput {
+        lines: vec![
+            (0, "position startpos\n"),
+            (0, "go ponder wtime 3000 btime 3000\n"),
+            (100, "isready\n"),
+            (100, "ponderhit\n"),
+            // Answered while the search uses its time after the ponder hit
+            (10, "isready\n"),
+        ],
+    };
+    let mut out_buf: Vec<u8> = Vec::new();
+    let start = std::time::Instant::now();
+    UciState::new()
+        .run_uci_input(&mut input, &mut out_buf)
+        .unwrap();
+    let output = without_info(&out_buf);
+    assert!(start.elapsed() < std::time::Duration::from_secs(3));
+    assert!(
+        output.starts_with("readyok\nreadyok\nbestmove "),
+        "{}",
+        output
+    );
+}
+
+#[test]
+fn engine_quits_during_search() {
+    let start = std::time::Instant::now();
+    let output = without_info(
+        uci_output(
+            &mut UciState::new(),
+            "position startpos\ngo depth 60\nquit\nisready\n",
+        )
+        .as_bytes(),
+    );
+    assert!(start.elapsed() < std::time::Duration::from_secs(5));
+    assert!(output.starts_with("bestmove "), "{}", output);
+    assert!(!output.contains("readyok"));
+}
+
+#[test]
+fn new_search_replaces_running_search() {
+    let output = without_info(
+        uci_output(
+            &mut UciState::new(),
+            "position startpos\ngo infinite\nposition fen 6k1/8/6K1/8/8/8/8/4R3 w - - 0 1\ngo depth 4\n",
+        )
+        .as_bytes(),
+    );
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].starts_with("bestmove "));
+    assert_eq!(lines[1], "bestmove e1e8");
+}
+
+#[test]
+fn table_is_changed_after_running_search_stops() {
+    // Each command stops the running search before the table is cleared, resized or aged
+    let output = uci_output(
+        &mut UciState::new(),
+        "position startpos\ngo infinite\nucinewgame\n\
+         position startpos\ngo infinite\nsetoption name Hash value 2\n\
+         position startpos\ngo infinite\n\
+         position fen 6k1/8/6K1/8/8/8/8/4R3 w - - 0 1\ngo depth 4\n",
+    );
+    assert!(!output.contains("info string"), "{}", output);
+    let output = without_info(output.as_bytes());
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(lines.len(), 4, "{}", output);
+    assert!(lines[..3].iter().all(|line| line.starts_with("bestmove ")));
+    assert_eq!(lines[3], "bestmove e1e8");
+}
+
+#[test]
+fn engine_reports_search_info() {
+    let output = uci_output(
+        &mut UciState::new(),
+        "position fen 6k1/5ppp/8/8/8/8/5PPP/3RR1K1 w - - 0 1\ngo depth 3\n",
+    );
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(lines.len(), 4, "{}", output);
+    for (i, line) in lines[..3].iter().enumerate() {
+        assert!(line.starts_with(&format!("info depth {} seldepth ", i + 1)));
+        for field in [
+            " score ",
+            " nodes ",
+            " nps ",
+            " time ",
+            " hashfull ",
+            " pv ",
+        ] {
+            assert!(line.contains(field), "{}", line);
+        }
+    }
+    assert!(lines[2].contains(" score mate 1 "), "{}", lines[2]);
+    assert!(lines[2].ends_with(" pv d1d8"), "{}", lines[2]);
+    assert_eq!(lines[3], "bestmove d1d8");
+}
+
+#[test]
+fn engine_reports_being_mated_from_black_perspective() {
+    let output = uci_output(
+        &mut UciState::new(),
+        "position fen k7/8/1K6/8/8/8/8/7R b - - 0 1\ngo depth 4\n",
+    );
+    let last_info = output
+        .lines()
+        .filter(|l| l.starts_with("info"))
+        .last()
+        .unwrap();
+    assert!(last_info.contains(" score mate -1 "), "{}", output);
+}
+
+#[test]
+fn invalid_options_are_reported() {
+    let output = uci_output(
+        &mut UciState::new(),
+        "setoption name Hash value 0\n\
+         setoption name Hash value lots\n\
+         setoption name Debug value maybe\n\
+         setoption name Contempt value 10\n\
+         isready\n",
+    );
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(lines.len(), 5, "{}", output);
+    for line in &lines[..4] {
+        assert!(line.starts_with("info string "), "{}", line);
+    }
+    assert_eq!(lines[4], "readyok");
+}
+
+#[test]
+fn options_are_applied() {
+    let output = uci_output(
+        &mut UciState::new(),
+        "setoption name hash value 2\n\
+         setoption name Threads value 3\n\
+         setoption name Move Overhead value 0\n\
+         setoption name Debug value false\n\
+         setoption name MultiPV value 3\n\
+         position startpos\n\
+         go depth 2\n",
+    );
+    assert!(!output.contains("info string"), "{}", output);
+    let last_depth: Vec<&str> = output
+        .lines()
+        .filter(|line| line.starts_with("info depth 2 "))
+        .collect();
+    assert_eq!(last_depth.len(), 3, "{}", output);
+    for (i, line) in last_depth.iter().enumerate() {
+        assert!(line.contains(&format!(" multipv {} ", i + 1)), "{}", line);
+    }
+    assert!(output.lines().last().unwrap().starts_with("bestmove "));
+}
+
+#[test]
+fn search_feature_options_are_applied() {
+    let nodes = |options: &str| {
+        let output = uci_output(
+            &mut UciState::new(),
+            &format!("{}position startpos\ngo depth 5\n", options),
+        );
+        assert!(!output.contains("info string"), "{}", output);
+        let last_depth = output
+            .lines()
+            .filter(|line| line.starts_with("info depth 5 "))
+            .last()
+            .unwrap_or_else(|| panic!("{}", output));
+        let mut words = last_depth.split_whitespace();
+        words.find(|&word| word == "nodes");
+        words.next().unwrap().parse::<u64>().unwrap()
+    };
+
+    let all = nodes("");
+    let none = nodes(
+        "setoption name Quiet Move Ordering value false\n\
+         setoption name Null Move Pruning value false\n\
+         setoption name Null Move Verification value false\n\
+         setoption name Late Move Reductions value false\n\
+         setoption name Futility Pruning value false\n\
+         setoption name Reverse Futility Pruning value false\n\
+         setoption name Principal Variation Search value false\n",
+    );
+    assert!(all < none, "{} >= {}", all, none);
+}
+
+#[test]
+fn multi_pv_is_limited_by_legal_moves() {
+    let output = uci_output(
+        &mut UciState::new(),
+        "setoption name MultiPV value 10\n\
+         position fen k7/8/1K6/8/8/8/8/7R b - - 0 1\n\
+         go depth 2\n",
+    );
+    assert_eq!(
+        without_info(output.as_bytes()),
+        "bestmove a8b8\n",
+        "{}",
+        output
+    );
+    assert_eq!(
+        output.lines().filter(|l| l.contains(" multipv 2 ")).count(),
+        0
+    );
+}
+
+fn assert_info_string(output: &str, line: usize) {
+    let line = output.lines().nth(line).unwrap_or("");
+    assert!(line.starts_with("info string "), "{}", output);
+}
+
+#[test]
+fn invalid_fen_is_reported() {
+    let mut state = UciState::new();
+    let output = uci_output(
+        &mut state,
+        "position fen rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP w KQkq - 0 1\ngo depth 1\n",
+    );
+    assert_info_string(&output, 0);
+    assert_info_string(&output, 1);
+    assert_eq!(output.lines().nth(2), Some("bestmove 0000"));
+
+    // Still usable afterwards
+    let output = uci_output(&mut state, "position startpos\ngo depth 1\n");
+    assert!(without_info(output.as_bytes()).starts_with("bestmove "));
+}
+
+#[test]
+fn position_without_startpos_or_fen_is_reported() {
+    // The parser rejects such a command, but the message can still be built directly
+    let mut state = UciState::new();
+    assert_eq!(
+        state.position(false, None, Vec::new()),
+        Err(UciError::MissingPosition)
+    );
+    assert_eq!(
+        UciError::MissingPosition.to_string(),
+        "Expected startpos or fen in position"
+    );
+}
+
+#[test]
+fn position_with_king_capture_is_reported() {
+    // The side not to move is in check, the king could be captured
+    let mut state = UciState::new();
+    let output = uci_output(
+        &mut state,
+        "position fen 4k3/8/8/8/8/8/4R3/4K3 w - - 0 1\ngo depth 3\n",
+    );
+    assert_info_string(&output, 0);
+    assert!(
+        output.contains("side not to move is in check"),
+        "{}",
+        output
+    );
+    assert_info_string(&output, 1);
+    assert_eq!(output.lines().nth(2), Some("bestmove 0000"));
+}
+
+#[test]
+fn illegal_moves_are_reported() {
+    for moves in ["e2e5", "e1g1", "e2e4 e7e5 d1d8", "e2e4 e2e4", "a7a8q"] {
+        let output = uci_output(
+            &mut UciState::new(),
+            &format!("position startpos moves {}\ngo depth 1\n", moves),
+        );
+        assert_info_string(&output, 0);
+        assert!(output.contains("Illegal move"), "{}", output);
+        assert!(output.ends_with("bestmove 0000\n"), "{}", output);
+    }
+}
+
+#[test]
+fn uci_move_text() {
+    let board = Board::default();
+    for text in ["e2e4", "g1f3", "b1a3"] {
+        assert_eq!(board.parse_uci_move(text).unwrap().to_uci(), text);
+    }
+    assert_eq!(
+        board.parse_uci_move("e2e4"),
+        Ok(Move::new(12, 28, false, 0b1))
+    );
+    for text in ["e2e5", "e7e5", "e2", "e2e4q", "", "z9e4"] {
+        assert_eq!(
+            board.parse_uci_move(text),
+            Err(IllegalMoveError(text.to_string()))
+        );
+    }
+
+    let castling = Board::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+    assert_eq!(
+        castling.parse_uci_move("e1g1"),
+        Ok(Move::new(4, 6, false, 0b10))
+    );
+    assert_eq!(
+        castling.parse_uci_move("e1c1"),
+        Ok(Move::new(4, 2, false, 0b11))
+    );
+
+    let promotion = Board::from_fen("1r2k3/P7/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+    for text in ["a7a8q", "a7a8r", "a7a8b", "a7a8n", "a7b8n", "a7b8q"] {
+        assert_eq!(promotion.parse_uci_move(text).unwrap().to_uci(), text);
+    }
+    assert!(promotion.parse_uci_move("a7a8").is_err());
+    assert!(promotion.parse_uci_move("a7a8k").is_err());
+}
+
+#[test]
+fn moving_into_check_is_illegal() {
+    let output = uci_output(
+        &mut UciState::new(),
+        "position fen 4k3/8/8/8/8/8/3r4/4K3 w - - 0 1 moves e1d1\n",
+    );
+    assert_info_string(&output, 0);
+}
+
+#[test]
+fn go_without_position_replies_null_move() {
+    let output = uci_output(&mut UciState::new(), "go depth 2\n");
+    assert_info_string(&output, 0);
+    assert_eq!(output.lines().nth(1), Some("bestmove 0000"));
+}
+
+#[test]
+fn no_legal_moves_replies_null_move() {
+    for fen in [
+        "k7/1Q6/1K6/8/8/8/8/8 b - - 0 1", // Mated
+        "k7/8/1QK5/8/8/8/8/8 b - - 0 1",  // Stalemate
+    ] {
+        let output = uci_output(
+            &mut UciState::new(),
+            &format!("position fen {}\ngo depth 3\n", fen),
+        );
+        assert_eq!(
+            without_info(output.as_bytes()),
+            "bestmove 0000\n",
+            "{}",
+            fen
+        );
+        assert!(!output.contains("info string"), "{}", output);
+    }
+}
+
+#[test]
+fn unknown_and_unsupported_commands_are_reported() {
+    let output = uci_output(
+        &mut UciState::new(),
+        "\n\
+         xyzzy\n\
+         register later\n\
+         readyok\n\
+         isready\n",
+    );
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(lines.len(), 4, "{}", output);
+    for i in 0..3 {
+        assert_info_string(&output, i);
+    }
+    assert_eq!(lines[3], "readyok");
+}
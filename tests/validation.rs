@@ -0,0 +1,121 @@
+use fisk::board::Board;
+use fisk::fen::{FenError, FEN_DEFAULT_BOARD};
+use fisk::validation::PositionViolation::{self, *};
+
+fn fen(fen: &str) -> Board {
+    Board::from_fen(fen).unwrap()
+}
+
+fn violations(fenstr: &str) -> Vec<PositionViolation> {
+    fen(fenstr).validate()
+}
+
+fn unplayable(fenstr: &str) -> Vec<PositionViolation> {
+    match Board::from_fen(fenstr) {
+        Err(FenError::InsanePosition(violations)) => violations,
+        other => panic!("Expected an insane position, got {:?}", other),
+    }
+}
+
+#[test]
+fn sane_positions() {
+    for fenstr in [
+        FEN_DEFAULT_BOARD,
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "rnbqkbnr/pppp1ppp/8/8/3pP3/8/PPP2PPP/RNBQKBNR b KQkq e3 0 3",
+        // Two queens after a promotion
+        "rnbqkbnr/ppp1pppp/8/8/8/8/PPPP1PPP/RNBQKQNR w - - 0 1",
+    ]
+    .iter()
+    {
+        assert_eq!(violations(fenstr), vec![], "{}", fenstr);
+        assert!(fen(fenstr).is_sane_position());
+    }
+}
+
+#[test]
+fn opponent_in_check() {
+    assert_eq!(violations("4k3/8/8/8/8/8/4R3/4K3 b - - 0 1"), vec![]);
+    // The king could be captured
+    assert_eq!(
+        unplayable("4k3/8/8/8/8/8/4R3/4K3 w - - 0 1"),
+        vec![OpponentInCheck]
+    );
+}
+
+#[test]
+fn kings_adjacent() {
+    assert_eq!(
+        unplayable("8/8/8/3kK3/8/8/8/8 w - - 0 1"),
+        vec![OpponentInCheck, KingsAdjacent]
+    );
+}
+
+#[test]
+fn impossible_piece_counts() {
+    // Eight pawns left, so no promotions could have happened
+    assert_eq!(
+        violations("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKQNR w - - 0 1"),
+        vec![ImpossiblePieceCount { white: true }]
+    );
+    assert_eq!(
+        violations("rnbqkbnr/pppppppp/nnnnnnnn/8/8/NNNNNNNN/PPPPPPPP/RNBQKBNR w - - 0 1"),
+        vec![
+            ImpossiblePieceCount { white: true },
+            ImpossiblePieceCount { white: false }
+        ]
+    );
+    assert_eq!(
+        unplayable("4k3/pppppppp/p7/8/8/8/8/4K3 w - - 0 1"),
+        vec![TooManyPawns {
+            white: false,
+            count: 9
+        }]
+    );
+}
+
+#[test]
+fn castling_rights() {
+    assert_eq!(
+        unplayable("r3k3/8/8/8/8/8/8/R3K2R w KQkq - 0 1"),
+        vec![CastlingRight { right: 'k' }]
+    );
+    assert_eq!(
+        unplayable("r3k2r/8/8/8/8/8/8/R4K1R w KQkq - 0 1"),
+        vec![CastlingRight { right: 'K' }, CastlingRight { right: 'Q' }]
+    );
+    // A queen on the rook square doesn't count
+    assert_eq!(
+        unplayable("r3k2r/8/8/8/8/8/8/R3K2Q w KQkq - 0 1"),
+        vec![CastlingRight { right: 'K' }]
+    );
+}
+
+#[test]
+fn en_passant() {
+    // No black pawn on d5
+    assert_eq!(
+        unplayable("4k3/8/8/8/8/8/8/4K3 w - d6 0 1"),
+        vec![EnPassant { square: 43 }]
+    );
+    // The pawn could not have passed a piece on e3
+    assert_eq!(
+        unplayable("4k3/8/8/8/4P3/4N3/8/4K3 b - e3 0 1"),
+        vec![EnPassant { square: 20 }]
+    );
+}
+
+#[test]
+fn king_counts_and_back_rank_pawns() {
+    assert_eq!(
+        unplayable("4k3/8/8/8/8/8/8/4KK2 w - - 0 1"),
+        vec![KingCount {
+            white: true,
+            count: 2
+        }]
+    );
+    assert_eq!(
+        unplayable("P3k3/8/8/8/8/8/8/4K2p w - - 0 1"),
+        vec![PawnOnBackRank { square: 7 }, PawnOnBackRank { square: 56 }]
+    );
+}
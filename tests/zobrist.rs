@@ -0,0 +1,93 @@
+use fisk::board::Board;
+use fisk::constants::SQUARE_NAME;
+use fisk::fen::FEN_DEFAULT_BOARD;
+use fisk::move_representation::Move;
+
+fn fen(fen: &str) -> Board {
+    Board::from_fen(fen).unwrap()
+}
+
+fn get_move(from: &str, to: &str, capture: bool, special_bits: u8) -> Move {
+    let from_tzcnt = SQUARE_NAME.iter().position(|x| *x == from).unwrap() as u8;
+    let to_tzcnt = SQUARE_NAME.iter().position(|x| *x == to).unwrap() as u8;
+
+    Move::new(from_tzcnt, to_tzcnt, capture, special_bits)
+}
+
+#[test]
+fn default_board_key_matches_fen() {
+    let a = Board::default();
+    let b = fen(FEN_DEFAULT_BOARD);
+    assert_eq!(a.zobrist(), b.zobrist());
+    assert_eq!(a.zobrist(), a.compute_zobrist());
+}
+
+#[test]
+fn state_is_part_of_key() {
+    let white = fen("4k3/8/8/8/8/8/8/R3K2R w KQ - 0 1");
+    let black = fen("4k3/8/8/8/8/8/8/R3K2R b KQ - 0 1");
+    let no_castling = fen("4k3/8/8/8/8/8/8/R3K2R w - - 0 1");
+    assert_ne!(white.zobrist(), black.zobrist());
+    assert_ne!(white.zobrist(), no_castling.zobrist());
+
+    let ep = fen("rnbqkbnr/1ppp1ppp/p7/3Pp3/8/8/PPP1PPPP/RNBQKBNR w KQkq e6 0 1");
+    let no_ep = fen("rnbqkbnr/1ppp1ppp/p7/3Pp3/8/8/PPP1PPPP/RNBQKBNR w KQkq - 0 1");
+    assert_ne!(ep.zobrist(), no_ep.zobrist());
+}
+
+#[test]
+fn clocks_are_not_part_of_key() {
+    let a = fen("4k3/8/8/8/8/8/8/R3K2R w KQ - 0 1");
+    let b = fen("4k3/8/8/8/8/8/8/R3K2R w KQ - 12 40");
+    assert_eq!(a.zobrist(), b.zobrist());
+}
+
+#[test]
+fn transposition_has_same_key() {
+    let b = Board::default();
+    let a1 = b
+        .make_move(&get_move("g1", "f3", false, 0))
+        .make_move(&get_move("g8", "f6", false, 0))
+        .make_move(&get_move("b1", "c3", false, 0));
+    let a2 = b
+        .make_move(&get_move("b1", "c3", false, 0))
+        .make_move(&get_move("g8", "f6", false, 0))
+        .make_move(&get_move("g1", "f3", false, 0));
+    assert_eq!(a1.zobrist(), a2.zobrist());
+    assert_ne!(a1.zobrist(), b.zobrist());
+}
+
+#[test]
+fn incremental_key_matches_recomputation() {
+    // Covers castling, promotion with capture and en passant captures
+    let boards = [
+        fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1"),
+        fen("r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1"),
+        fen("2k5/4p3/8/3P4/8/8/8/1K6 b - - 0 1"),
+    ];
+    for board in &boards {
+        for s in board.generate_successors() {
+            assert_eq!(s.zobrist(), s.compute_zobrist());
+            for ss in s.generate_successors() {
+                assert_eq!(ss.zobrist(), ss.compute_zobrist());
+            }
+        }
+    }
+}
+
+#[test]
+fn null_move_updates_key() {
+    let mut board = fen("rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR b KQkq - 3 2");
+    let before = board;
+    let undo = board.make_null_move();
+    assert!(board.white_to_move());
+    assert_eq!(
+        board.to_fen(),
+        "rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq - 3 3"
+    );
+    assert_eq!(board.zobrist(), board.compute_zobrist());
+
+    board.unmake_null_move(&undo);
+    assert_eq!(board.to_fen(), before.to_fen());
+    assert_eq!(board.zobrist(), before.zobrist());
+}